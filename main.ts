@@ -25,14 +25,67 @@ setInterval(() => {
 }, 1000);
 
 init().then(() => {
-    const game = new Game({
-        devMode: true,
-        container: document.getElementById("game") ?? undefined,
-    });
+    let game: Game;
+
+    try {
+        game = new Game({
+            devMode: true,
+            container: document.getElementById("game") ?? undefined,
+        });
+    } catch (error) {
+        const code = (error as { code?: string }).code ?? 'UNKNOWN';
+        const message = document.createElement('p');
+
+        message.textContent = `Failed to start the game (${code})`;
+        document.getElementById('game')?.append(message);
+        console.error(error);
+        return;
+    }
 
     game.addEventListener('frame', measureFPS)
 
     document.getElementById('run')?.addEventListener('click', () => game.run());
     document.getElementById('stop')?.addEventListener('click', () => game.stop());
     document.getElementById('reset')?.addEventListener('click', () => game.reset());
+    document.getElementById('orthographic')?.addEventListener('change', (event) => {
+        game.orthographic = (event.target as HTMLInputElement).checked;
+    });
+    document.getElementById('pointer-lock')?.addEventListener('click', () => game.requestPointerLock());
+    document.getElementById('virtual-joystick')?.addEventListener('change', (event) => {
+        game.virtualJoystick = (event.target as HTMLInputElement).checked;
+    });
+
+    const sliders = {
+        fov: document.getElementById('fov') as HTMLInputElement,
+        zNear: document.getElementById('z-near') as HTMLInputElement,
+        zFar: document.getElementById('z-far') as HTMLInputElement,
+    };
+
+    const syncSliders = () => {
+        sliders.fov.value = String(Math.round(game.fov * 180 / Math.PI));
+        sliders.zNear.value = String(game.zNear);
+        sliders.zFar.value = String(game.zFar);
+    };
+
+    const setProjection = (update: () => void) => {
+        try {
+            update();
+        } catch (error) {
+            console.warn(error);
+            syncSliders();
+        }
+    };
+
+    sliders.fov.addEventListener('change', () => setProjection(() => {
+        game.fov = Number(sliders.fov.value) * Math.PI / 180;
+    }));
+    sliders.zNear.addEventListener('change', () => setProjection(() => {
+        game.zNear = Number(sliders.zNear.value);
+    }));
+    sliders.zFar.addEventListener('change', () => setProjection(() => {
+        game.zFar = Number(sliders.zFar.value);
+    }));
+
+    game.addEventListener('projectionchange', syncSliders);
+    syncSliders();
 });
\ No newline at end of file
@@ -0,0 +1,525 @@
+use std::{collections::HashMap, fmt};
+
+use super::{
+    error::AnimationError,
+    sprite::{Sprite, TextureAtlas},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PlaybackMode {
+    #[default]
+    Loop,
+    /// Plays forward then backward, without repeating the first and last frames.
+    PingPong,
+    /// Stops on the last frame.
+    Once,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationFrame {
+    /// Name of the frame in the texture atlas.
+    pub name: String,
+    /// In seconds.
+    pub duration: f64,
+}
+
+impl AnimationFrame {
+    pub fn new(name: impl Into<String>, duration: f64) -> Self {
+        Self {
+            name: name.into(),
+            duration,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Animation {
+    pub frames: Vec<AnimationFrame>,
+    pub mode: PlaybackMode,
+}
+
+impl Animation {
+    pub fn new(frames: Vec<AnimationFrame>, mode: PlaybackMode) -> Self {
+        Self { frames, mode }
+    }
+
+    /// Every frame lasts `frame_duration` seconds.
+    pub fn uniform<S: Into<String>>(
+        names: impl IntoIterator<Item = S>,
+        frame_duration: f64,
+        mode: PlaybackMode,
+    ) -> Self {
+        Self::new(
+            names
+                .into_iter()
+                .map(|name| AnimationFrame::new(name, frame_duration))
+                .collect(),
+            mode,
+        )
+    }
+
+    /// Frames of `atlas` whose name starts with `prefix`, in lexicographic order.
+    pub fn from_atlas(
+        atlas: &TextureAtlas,
+        prefix: &str,
+        frame_duration: f64,
+        mode: PlaybackMode,
+    ) -> Self {
+        Self::uniform(
+            atlas.names().filter(|name| name.starts_with(prefix)),
+            frame_duration,
+            mode,
+        )
+    }
+
+    /// Duration of one pass, forward and back for ping-pong.
+    pub fn cycle_duration(&self) -> f64 {
+        self.sequence().map(|i| self.frames[i].duration).sum()
+    }
+
+    /// Index of the frame shown `time` seconds after the start.
+    pub fn frame_at(&self, time: f64) -> Option<usize> {
+        let cycle = self.cycle_duration();
+
+        if self.frames.is_empty() {
+            return None;
+        }
+
+        if cycle <= 0.0 || (self.mode == PlaybackMode::Once && time >= cycle) {
+            return Some(self.frames.len() - 1);
+        }
+
+        let mut remaining = time.rem_euclid(cycle);
+
+        for i in self.sequence() {
+            if remaining < self.frames[i].duration {
+                return Some(i);
+            }
+
+            remaining -= self.frames[i].duration;
+        }
+
+        self.sequence().last()
+    }
+
+    fn sequence(&self) -> impl Iterator<Item = usize> {
+        let n = self.frames.len();
+        let back = match self.mode {
+            PlaybackMode::PingPong => (1..n.saturating_sub(1)).rev(),
+            _ => (0..0).rev(),
+        };
+
+        (0..n).chain(back)
+    }
+}
+
+/// What switches the player from one state to another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Trigger {
+    /// The current animation completed a pass.
+    Finished,
+    /// Named event passed to `AnimationPlayer::trigger`.
+    Event(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Transition {
+    /// `None` applies from any state.
+    from: Option<String>,
+    to: String,
+    trigger: Trigger,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Playing {
+    state: String,
+    elapsed: f64,
+    finished: bool,
+}
+
+type CompleteCallback = Box<dyn FnMut(&str)>;
+
+/// Plays named animation states and moves between them through transitions.
+pub struct AnimationPlayer {
+    states: HashMap<String, Animation>,
+    transitions: Vec<Transition>,
+    playing: Option<Playing>,
+    on_complete: Vec<CompleteCallback>,
+    /// Multiplier applied to the update delta.
+    pub speed: f64,
+}
+
+impl AnimationPlayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_state(&mut self, name: &str, animation: Animation) -> Result<(), AnimationError> {
+        if animation.frames.is_empty() {
+            return Err(AnimationError::NoFrames(name.to_string()));
+        }
+
+        self.states.insert(name.to_string(), animation);
+
+        Ok(())
+    }
+
+    pub fn add_transition(
+        &mut self,
+        from: &str,
+        to: &str,
+        trigger: Trigger,
+    ) -> Result<(), AnimationError> {
+        self.check_state(from)?;
+        self.add(Some(from), to, trigger)
+    }
+
+    /// Transition taken from any state without a more specific one for the same trigger.
+    pub fn add_any_transition(&mut self, to: &str, trigger: Trigger) -> Result<(), AnimationError> {
+        self.add(None, to, trigger)
+    }
+
+    /// Called with the state name each time an animation completes a pass.
+    pub fn on_complete(&mut self, callback: impl FnMut(&str) + 'static) {
+        self.on_complete.push(Box::new(callback));
+    }
+
+    /// Starts `state` from its first frame, unless it is already playing and not finished.
+    pub fn play(&mut self, state: &str) -> Result<(), AnimationError> {
+        self.check_state(state)?;
+
+        if self.state() != Some(state) || self.is_finished() {
+            self.playing = Some(Playing {
+                state: state.to_string(),
+                elapsed: 0.0,
+                finished: false,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Follows the transition for `event` out of the current state, returns whether there was one.
+    pub fn trigger(&mut self, event: &str) -> bool {
+        let to = self.transition(&Trigger::Event(event.to_string()));
+
+        to.is_some_and(|to| self.play(&to).is_ok())
+    }
+
+    /// Advances the current animation by `delta` seconds.
+    pub fn update(&mut self, delta: f64) {
+        let Some(playing) = self.playing.as_mut().filter(|playing| !playing.finished) else {
+            return;
+        };
+
+        let animation = &self.states[&playing.state];
+        let cycle = animation.cycle_duration();
+        let previous = playing.elapsed;
+
+        playing.elapsed += delta * self.speed;
+
+        let completions = if cycle <= 0.0 {
+            0
+        } else if animation.mode == PlaybackMode::Once {
+            playing.finished = playing.elapsed >= cycle;
+            usize::from(playing.finished)
+        } else {
+            ((playing.elapsed / cycle).floor() - (previous / cycle).floor()).max(0.0) as usize
+        };
+
+        if completions == 0 {
+            return;
+        }
+
+        let state = playing.state.clone();
+
+        for _ in 0..completions {
+            for callback in &mut self.on_complete {
+                callback(&state);
+            }
+        }
+
+        if let Some(to) = self.transition(&Trigger::Finished) {
+            self.play(&to).ok();
+        }
+    }
+
+    pub fn state(&self) -> Option<&str> {
+        self.playing.as_ref().map(|playing| playing.state.as_str())
+    }
+
+    /// Whether a `Once` animation reached its last frame.
+    pub fn is_finished(&self) -> bool {
+        self.playing
+            .as_ref()
+            .is_some_and(|playing| playing.finished)
+    }
+
+    pub fn frame(&self) -> Option<&AnimationFrame> {
+        let playing = self.playing.as_ref()?;
+        let animation = &self.states[&playing.state];
+
+        animation
+            .frame_at(playing.elapsed)
+            .map(|i| &animation.frames[i])
+    }
+
+    /// Sprite of the current frame, see `TextureAtlas::sprite`.
+    pub fn sprite(&self, atlas: &TextureAtlas, x: f64, y: f64) -> Option<Sprite> {
+        atlas.sprite(&self.frame()?.name, x, y)
+    }
+
+    fn add(
+        &mut self,
+        from: Option<&str>,
+        to: &str,
+        trigger: Trigger,
+    ) -> Result<(), AnimationError> {
+        self.check_state(to)?;
+        self.transitions.push(Transition {
+            from: from.map(str::to_string),
+            to: to.to_string(),
+            trigger,
+        });
+
+        Ok(())
+    }
+
+    fn check_state(&self, state: &str) -> Result<(), AnimationError> {
+        if self.states.contains_key(state) {
+            Ok(())
+        } else {
+            Err(AnimationError::UnknownState(state.to_string()))
+        }
+    }
+
+    fn transition(&self, trigger: &Trigger) -> Option<String> {
+        let state = self.state()?;
+        let matching = |from: Option<&str>| {
+            self.transitions
+                .iter()
+                .find(|t| t.from.as_deref() == from && &t.trigger == trigger)
+        };
+
+        matching(Some(state))
+            .or_else(|| matching(None).filter(|t| t.to != state))
+            .map(|t| t.to.clone())
+    }
+}
+
+impl Default for AnimationPlayer {
+    fn default() -> Self {
+        Self {
+            states: HashMap::new(),
+            transitions: vec![],
+            playing: None,
+            on_complete: vec![],
+            speed: 1.0,
+        }
+    }
+}
+
+impl fmt::Debug for AnimationPlayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AnimationPlayer")
+            .field("states", &self.states)
+            .field("transitions", &self.transitions)
+            .field("playing", &self.playing)
+            .field("speed", &self.speed)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+
+    fn frames(mode: PlaybackMode) -> Animation {
+        Animation::new(
+            vec![
+                AnimationFrame::new("a", 0.1),
+                AnimationFrame::new("b", 0.2),
+                AnimationFrame::new("c", 0.1),
+            ],
+            mode,
+        )
+    }
+
+    fn player() -> AnimationPlayer {
+        let mut player = AnimationPlayer::new();
+
+        player
+            .add_state("idle", frames(PlaybackMode::Loop))
+            .unwrap();
+        player
+            .add_state("run", frames(PlaybackMode::PingPong))
+            .unwrap();
+        player
+            .add_state("jump", frames(PlaybackMode::Once))
+            .unwrap();
+        player
+    }
+
+    #[test]
+    fn test_loop() {
+        let animation = frames(PlaybackMode::Loop);
+
+        assert!((animation.cycle_duration() - 0.4).abs() < 1e-9);
+        assert_eq!(animation.frame_at(0.0), Some(0));
+        assert_eq!(animation.frame_at(0.15), Some(1));
+        assert_eq!(animation.frame_at(0.35), Some(2));
+        assert_eq!(animation.frame_at(0.45), Some(0));
+    }
+
+    #[test]
+    fn test_ping_pong() {
+        let animation = frames(PlaybackMode::PingPong);
+
+        assert!((animation.cycle_duration() - 0.6).abs() < 1e-9);
+        assert_eq!(animation.frame_at(0.45), Some(1));
+        assert_eq!(animation.frame_at(0.65), Some(0));
+    }
+
+    #[test]
+    fn test_once() {
+        let animation = frames(PlaybackMode::Once);
+
+        assert_eq!(animation.frame_at(0.35), Some(2));
+        assert_eq!(animation.frame_at(10.0), Some(2));
+        assert_eq!(Animation::default().frame_at(0.0), None);
+    }
+
+    #[test]
+    fn test_player_update() {
+        let mut player = player();
+        let completed = Rc::new(RefCell::new(vec![]));
+
+        player.on_complete({
+            let completed = completed.clone();
+
+            move |state| completed.borrow_mut().push(state.to_string())
+        });
+        player.play("idle").unwrap();
+        player.update(0.15);
+
+        assert_eq!(player.frame().map(|f| f.name.as_str()), Some("b"));
+
+        player.update(0.7);
+
+        assert_eq!(*completed.borrow(), ["idle", "idle"]);
+        assert!(!player.is_finished());
+    }
+
+    #[test]
+    fn test_transitions() {
+        let mut player = player();
+
+        player
+            .add_transition("idle", "run", Trigger::Event("move".to_string()))
+            .unwrap();
+        player
+            .add_transition("run", "idle", Trigger::Event("stop".to_string()))
+            .unwrap();
+        player
+            .add_any_transition("jump", Trigger::Event("jump".to_string()))
+            .unwrap();
+        player
+            .add_transition("jump", "idle", Trigger::Finished)
+            .unwrap();
+        player.play("idle").unwrap();
+
+        assert!(!player.trigger("stop"));
+        assert!(player.trigger("move"));
+        assert_eq!(player.state(), Some("run"));
+        assert!(player.trigger("jump"));
+        assert_eq!(player.state(), Some("jump"));
+        assert!(!player.trigger("jump"));
+
+        player.update(0.5);
+
+        assert_eq!(player.state(), Some("idle"));
+        assert_eq!(player.frame().map(|f| f.name.as_str()), Some("a"));
+    }
+
+    #[test]
+    fn test_once_stops_without_transition() {
+        let mut player = player();
+
+        player.play("jump").unwrap();
+        player.update(1.0);
+
+        assert!(player.is_finished());
+        assert_eq!(player.frame().map(|f| f.name.as_str()), Some("c"));
+    }
+
+    #[test]
+    fn test_replay_finished() {
+        let mut player = player();
+
+        player.play("jump").unwrap();
+        player.update(0.35);
+        player.play("jump").unwrap();
+
+        // still playing, so it carries on
+        assert_eq!(player.frame().map(|f| f.name.as_str()), Some("c"));
+
+        player.update(1.0);
+        player.play("jump").unwrap();
+
+        assert!(!player.is_finished());
+        assert_eq!(player.frame().map(|f| f.name.as_str()), Some("a"));
+
+        player.update(1.0);
+
+        assert!(player.is_finished());
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut player = player();
+
+        assert_eq!(
+            player.play("swim"),
+            Err(AnimationError::UnknownState("swim".to_string()))
+        );
+        assert_eq!(
+            player.add_state("empty", Animation::default()),
+            Err(AnimationError::NoFrames("empty".to_string()))
+        );
+        assert!(player
+            .add_transition("idle", "swim", Trigger::Finished)
+            .is_err());
+    }
+
+    #[test]
+    fn test_from_atlas() {
+        let atlas: TextureAtlas = r#"{ "frames": [
+            { "filename": "run_1", "frame": { "x": 16, "y": 0, "w": 16, "h": 16 } },
+            { "filename": "idle", "frame": { "x": 32, "y": 0, "w": 16, "h": 16 } },
+            { "filename": "run_0", "frame": { "x": 0, "y": 0, "w": 16, "h": 16 } }
+        ] }"#
+            .parse()
+            .unwrap();
+        let animation = Animation::from_atlas(&atlas, "run_", 0.1, PlaybackMode::Loop);
+        let mut player = AnimationPlayer::new();
+
+        assert_eq!(
+            animation.frames,
+            [
+                AnimationFrame::new("run_0", 0.1),
+                AnimationFrame::new("run_1", 0.1)
+            ]
+        );
+
+        player.add_state("run", animation).unwrap();
+        player.play("run").unwrap();
+        player.update(0.15);
+
+        assert_eq!(
+            player.sprite(&atlas, 0.0, 0.0).map(|s| s.source.x),
+            Some(16.0)
+        );
+    }
+}
@@ -0,0 +1,385 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    rc::Rc,
+    str::FromStr,
+};
+
+use futures_util::{
+    future::{LocalBoxFuture, Shared},
+    stream::FuturesUnordered,
+    FutureExt, StreamExt,
+};
+use serde::{Deserialize, Serialize};
+use web_sys::HtmlImageElement;
+
+use super::{assets_loader::AssetsLoader, error::AssetError, obj::ObjModel};
+
+/// Assets to preload, by kind, mapping names to paths relative to the loader base path.
+///
+/// ```json
+/// { "images": { "player": "sprites/player.png" }, "meshes": { "ship": "ship.obj" } }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AssetManifest {
+    pub images: BTreeMap<String, String>,
+    pub meshes: BTreeMap<String, String>,
+    pub sounds: BTreeMap<String, String>,
+}
+
+impl AssetManifest {
+    pub fn len(&self) -> usize {
+        self.images.len() + self.meshes.len() + self.sounds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (AssetKind, &str, &str)> {
+        [
+            (AssetKind::Image, &self.images),
+            (AssetKind::Mesh, &self.meshes),
+            (AssetKind::Sound, &self.sounds),
+        ]
+        .into_iter()
+        .flat_map(|(kind, map)| {
+            map.iter()
+                .map(move |(name, path)| (kind, name.as_str(), path.as_str()))
+        })
+    }
+}
+
+impl FromStr for AssetManifest {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AssetKind {
+    Image,
+    Mesh,
+    Sound,
+}
+
+#[derive(Debug)]
+pub enum Asset {
+    Image(HtmlImageElement),
+    Mesh(ObjModel),
+    Sound(Vec<u8>),
+}
+
+/// Progress of a batch, `name` is the asset that settled last.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AssetProgress {
+    pub name: String,
+    pub loaded: usize,
+    pub failed: usize,
+    pub total: usize,
+}
+
+impl AssetProgress {
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            1.0
+        } else {
+            (self.loaded + self.failed) as f64 / self.total as f64
+        }
+    }
+}
+
+/// Load of one asset, shared by every preload waiting on it.
+type PendingLoad = Shared<LocalBoxFuture<'static, Result<(), AssetError>>>;
+
+/// Cache of loaded assets, looked up by the names of the manifest. Each kind has its own names,
+/// as the manifest lists them apart, so an image and a mesh may both be called `cube` and
+/// entries are told apart by kind and name.
+#[derive(Debug, Default)]
+pub struct Assets {
+    images: HashMap<String, HtmlImageElement>,
+    meshes: HashMap<String, ObjModel>,
+    sounds: HashMap<String, Vec<u8>>,
+    pending: HashMap<(AssetKind, String), PendingLoad>,
+}
+
+impl Assets {
+    pub fn image(&self, name: &str) -> Option<&HtmlImageElement> {
+        self.images.get(name)
+    }
+
+    pub fn mesh(&self, name: &str) -> Option<&ObjModel> {
+        self.meshes.get(name)
+    }
+
+    pub fn sound(&self, name: &str) -> Option<&[u8]> {
+        self.sounds.get(name).map(Vec::as_slice)
+    }
+
+    pub fn contains(&self, kind: AssetKind, name: &str) -> bool {
+        match kind {
+            AssetKind::Image => self.images.contains_key(name),
+            AssetKind::Mesh => self.meshes.contains_key(name),
+            AssetKind::Sound => self.sounds.contains_key(name),
+        }
+    }
+
+    pub fn insert(&mut self, name: &str, asset: Asset) {
+        let name = name.to_string();
+
+        match asset {
+            Asset::Image(image) => {
+                self.images.insert(name, image);
+            }
+            Asset::Mesh(mesh) => {
+                self.meshes.insert(name, mesh);
+            }
+            Asset::Sound(bytes) => {
+                self.sounds.insert(name, bytes);
+            }
+        }
+    }
+
+    /// Whether the asset is being loaded by a preload that has not settled yet.
+    pub fn is_pending(&self, kind: AssetKind, name: &str) -> bool {
+        self.pending.contains_key(&(kind, name.to_string()))
+    }
+
+    /// Loads every asset of `manifest` that is not cached yet concurrently, calling `on_progress`
+    /// as each one settles. Assets already being loaded by an earlier call are waited for rather
+    /// than fetched twice. Failed assets do not stop the others, the first error is returned at
+    /// the end.
+    pub async fn preload(
+        assets: &Rc<RefCell<Self>>,
+        loader: &Rc<AssetsLoader>,
+        manifest: &AssetManifest,
+        on_progress: impl FnMut(&AssetProgress),
+    ) -> Result<AssetProgress, AssetError> {
+        let load = |kind, path: String| {
+            let loader = loader.clone();
+
+            async move {
+                match kind {
+                    AssetKind::Image => loader.load_image(&path).await.map(Asset::Image),
+                    AssetKind::Mesh => loader.load_mesh(&path).await.map(Asset::Mesh),
+                    AssetKind::Sound => loader.load_bytes(&path).await.map(Asset::Sound),
+                }
+            }
+            .boxed_local()
+        };
+
+        Self::preload_with(assets, manifest, load, on_progress).await
+    }
+
+    /// `preload` fetching the asset of a kind at a path with `load`.
+    async fn preload_with(
+        assets: &Rc<RefCell<Self>>,
+        manifest: &AssetManifest,
+        load: impl Fn(AssetKind, String) -> LocalBoxFuture<'static, Result<Asset, AssetError>>,
+        mut on_progress: impl FnMut(&AssetProgress),
+    ) -> Result<AssetProgress, AssetError> {
+        let mut loading: FuturesUnordered<_> = {
+            let mut cache = assets.borrow_mut();
+            let missing: Vec<_> = manifest
+                .entries()
+                .filter(|(kind, name, _)| !cache.contains(*kind, name))
+                .collect();
+
+            missing
+                .into_iter()
+                .map(|(kind, name, path)| {
+                    cache
+                        .pending
+                        .entry((kind, name.to_string()))
+                        .or_insert_with(|| {
+                            Self::load(assets, kind, name, load(kind, path.to_string()))
+                        })
+                        .clone()
+                        .map(move |result| (name, result))
+                })
+                .collect()
+        };
+
+        let mut progress = AssetProgress {
+            total: loading.len(),
+            ..Default::default()
+        };
+        let mut error = None;
+
+        while let Some((name, result)) = loading.next().await {
+            match result {
+                Ok(()) => progress.loaded += 1,
+                Err(err) => {
+                    progress.failed += 1;
+                    error.get_or_insert(err);
+                }
+            }
+
+            progress.name = name.to_string();
+            on_progress(&progress);
+        }
+
+        match error {
+            Some(err) => Err(err),
+            None => Ok(progress),
+        }
+    }
+
+    /// Caches the asset `load` resolves to and forgets it is pending.
+    fn load(
+        assets: &Rc<RefCell<Self>>,
+        kind: AssetKind,
+        name: &str,
+        load: LocalBoxFuture<'static, Result<Asset, AssetError>>,
+    ) -> PendingLoad {
+        let assets = Rc::downgrade(assets);
+        let name = name.to_string();
+
+        async move {
+            let result = load.await;
+            let Some(assets) = assets.upgrade() else {
+                return result.map(|_| ());
+            };
+            let mut assets = assets.borrow_mut();
+
+            assets.pending.remove(&(kind, name.clone()));
+
+            match result {
+                Ok(asset) => {
+                    assets.insert(&name, asset);
+                    Ok(())
+                }
+                Err(err) => {
+                    log::error!("{err}");
+                    Err(err)
+                }
+            }
+        }
+        .boxed_local()
+        .shared()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::Cell,
+        future::{poll_fn, Future},
+        task::{Context, Poll},
+    };
+
+    use futures_util::task::noop_waker_ref;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest: AssetManifest = r#"{
+            "images": { "player": "sprites/player.png", "enemy": "sprites/enemy.png" },
+            "sounds": { "jump": "sounds/jump.ogg" }
+        }"#
+        .parse()
+        .unwrap();
+
+        assert_eq!(manifest.len(), 3);
+        assert!(manifest.meshes.is_empty());
+        assert_eq!(manifest.images["player"], "sprites/player.png");
+        assert_eq!(
+            manifest.entries().collect::<Vec<_>>(),
+            vec![
+                (AssetKind::Image, "enemy", "sprites/enemy.png"),
+                (AssetKind::Image, "player", "sprites/player.png"),
+                (AssetKind::Sound, "jump", "sounds/jump.ogg"),
+            ]
+        );
+        assert!("{\"images\": []}".parse::<AssetManifest>().is_err());
+    }
+
+    fn poll<F: Future + Unpin>(future: &mut F) -> Poll<F::Output> {
+        future.poll_unpin(&mut Context::from_waker(noop_waker_ref()))
+    }
+
+    #[test]
+    fn test_preload_waits_for_pending() {
+        let assets = Rc::new(RefCell::new(Assets::default()));
+        let requested = RefCell::new(Vec::new());
+        let fetched = Rc::new(Cell::new(false));
+        let load = |kind, path: String| {
+            let fetched = fetched.clone();
+
+            requested.borrow_mut().push(path);
+
+            poll_fn(move |cx| match (fetched.get(), kind) {
+                (false, _) => {
+                    cx.waker().wake_by_ref();
+                    Poll::Pending
+                }
+                (true, AssetKind::Sound) => Poll::Ready(Ok(Asset::Sound(vec![1]))),
+                (true, _) => Poll::Ready(Err(AssetError::NotFound(format!("{kind:?}")))),
+            })
+            .boxed_local()
+        };
+        let jump: AssetManifest = r#"{ "sounds": { "jump": "jump.ogg" } }"#.parse().unwrap();
+        let manifest: AssetManifest = r#"{
+            "meshes": { "cube": "cube.obj" },
+            "sounds": { "cube": "cube.ogg", "jump": "jump.ogg" }
+        }"#
+        .parse()
+        .unwrap();
+
+        assets
+            .borrow_mut()
+            .insert("cube", Asset::Mesh(ObjModel::default()));
+
+        let mut first = Box::pin(Assets::preload_with(&assets, &jump, &load, |_| {}));
+
+        assert!(poll(&mut first).is_pending());
+        assert!(assets.borrow().is_pending(AssetKind::Sound, "jump"));
+
+        let mut second = Box::pin(Assets::preload_with(&assets, &manifest, &load, |_| {}));
+
+        // the cached mesh is skipped and the sound still loading is not fetched again
+        assert!(poll(&mut second).is_pending());
+        assert_eq!(*requested.borrow(), ["jump.ogg", "cube.ogg"]);
+
+        fetched.set(true);
+
+        let Poll::Ready(Ok(progress)) = poll(&mut second) else {
+            panic!("expected the second preload to finish");
+        };
+
+        assert_eq!((progress.loaded, progress.total), (2, 2));
+        assert_eq!(assets.borrow().sound("jump"), Some(&[1][..]));
+        assert!(!assets.borrow().is_pending(AssetKind::Sound, "jump"));
+        assert!(matches!(poll(&mut first), Poll::Ready(Ok(progress)) if progress.loaded == 1));
+    }
+
+    #[test]
+    fn test_lookup() {
+        let mut assets = Assets::default();
+
+        assets.insert("jump", Asset::Sound(vec![1, 2, 3]));
+
+        assert_eq!(assets.sound("jump"), Some(&[1, 2, 3][..]));
+        assert!(assets.contains(AssetKind::Sound, "jump"));
+        assert!(!assets.contains(AssetKind::Mesh, "jump"));
+        assert!(assets.mesh("jump").is_none());
+        assert!(assets.image("jump").is_none());
+    }
+
+    #[test]
+    fn test_progress_fraction() {
+        let progress = AssetProgress {
+            loaded: 2,
+            failed: 1,
+            total: 4,
+            ..Default::default()
+        };
+
+        assert_eq!(progress.fraction(), 0.75);
+        assert_eq!(AssetProgress::default().fraction(), 1.0);
+    }
+}
@@ -1,14 +1,67 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, future::Future, rc::Rc, time::Duration};
 
-use js_sys::{Array, Function, Promise};
-use wasm_bindgen::{prelude::Closure, JsCast, JsValue, UnwrapThrowExt};
+use js_sys::{Array, Date, Function, Object, Promise, Uint8Array};
+use wasm_bindgen::{prelude::Closure, JsCast, JsValue};
 use wasm_bindgen_futures::JsFuture;
-use web_sys::{window, HtmlImageElement};
+use web_sys::{window, AbortController, Blob, HtmlImageElement, RequestInit, Response, Url};
 
-use super::error::InitError;
+use super::{
+    assets::AssetManifest,
+    error::{AssetError, InitError},
+    obj::{parse_mtl, parse_obj, ObjModel},
+};
 
 pub struct AssetsLoader {
     pub base_path: String,
+    /// Limit for a single attempt at loading an asset, `None` waits forever.
+    pub timeout: Option<Duration>,
+    pub retry: RetryPolicy,
+}
+
+/// Exponential backoff applied to failures that may succeed on another attempt.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one.
+    pub max_attempts: u32,
+    pub initial_delay: Duration,
+    pub multiplier: f64,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_attempts(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before the retry following the failed attempt number `attempt`, starting at 0.
+    /// A `multiplier` below 1 is taken as 1, the delays never shrink.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let multiplier = self.multiplier.max(1.0);
+        let delay = self.initial_delay.as_secs_f64() * multiplier.powi(attempt as i32);
+
+        Duration::from_secs_f64(delay.min(self.max_delay.as_secs_f64()))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_delay: Duration::from_millis(250),
+            multiplier: 2.0,
+            max_delay: Duration::from_secs(5),
+        }
+    }
 }
 
 impl AssetsLoader {
@@ -19,29 +72,138 @@ impl AssetsLoader {
         }
     }
 
-    pub async fn load_image(&self, name: &str) -> Result<HtmlImageElement, InitError> {
-        let document = window()
-            .ok_or(InitError::NoWindow)?
-            .document()
-            .ok_or(InitError::NoDocument)?;
+    pub fn with_timeout(self, timeout: Option<Duration>) -> Self {
+        Self { timeout, ..self }
+    }
 
-        let image = document
-            .create_element("img")
-            .map_err(|err| InitError::ElementCreationFailed(format!("{err:?}")))?
-            .unchecked_into::<HtmlImageElement>();
+    pub fn with_retry(self, retry: RetryPolicy) -> Self {
+        Self { retry, ..self }
+    }
 
+    pub async fn load_image(&self, name: &str) -> Result<HtmlImageElement, AssetError> {
         let src = format!("{}{}", self.base_path, name);
 
         log::debug!("loading image {}", &src);
 
-        image.set_src(&src);
+        self.retry(&src, || async {
+            let deadline = self.deadline();
+            let response = fetch(&src, deadline.as_ref()).await?;
+            let blob = wait(
+                response.blob().map_err(fetch_failed(&src))?,
+                &src,
+                deadline.as_ref(),
+            )
+            .await?
+            .map_err(fetch_failed(&src))?
+            .unchecked_into::<Blob>();
+
+            decode_image(&src, &blob, deadline.as_ref()).await
+        })
+        .await
+    }
+
+    /// Loads a Wavefront OBJ file and the material libraries it references, relative to it.
+    pub async fn load_mesh(&self, name: &str) -> Result<ObjModel, AssetError> {
+        let src = format!("{}{}", self.base_path, name);
+
+        log::debug!("loading mesh {}", &src);
+
+        let source = self.fetch_text(&src).await?;
+        let mut model = parse_obj(&source).map_err(|err| AssetError::ParseFailed(src, err))?;
+        let directory = name.rfind('/').map_or("", |i| &name[..=i]);
+
+        for material_lib in &model.material_libs {
+            let src = format!("{}{}{}", self.base_path, directory, material_lib);
+
+            log::debug!("loading materials {}", &src);
+
+            let source = self.fetch_text(&src).await?;
+            let materials = parse_mtl(&source).map_err(|err| AssetError::ParseFailed(src, err))?;
+
+            model.materials.extend(materials);
+        }
+
+        Ok(model)
+    }
+
+    /// Raw bytes of a file, e.g. an encoded sound.
+    pub async fn load_bytes(&self, name: &str) -> Result<Vec<u8>, AssetError> {
+        let src = format!("{}{}", self.base_path, name);
+
+        log::debug!("loading bytes {}", &src);
+
+        self.retry(&src, || async {
+            let deadline = self.deadline();
+            let response = fetch(&src, deadline.as_ref()).await?;
+            let buffer = wait(
+                response.array_buffer().map_err(fetch_failed(&src))?,
+                &src,
+                deadline.as_ref(),
+            )
+            .await?
+            .map_err(fetch_failed(&src))?;
+
+            Ok(Uint8Array::new(&buffer).to_vec())
+        })
+        .await
+    }
+
+    pub async fn load_manifest(&self, name: &str) -> Result<AssetManifest, AssetError> {
+        let src = format!("{}{}", self.base_path, name);
+
+        log::debug!("loading manifest {}", &src);
+
+        self.fetch_text(&src)
+            .await?
+            .parse()
+            .map_err(|err: serde_json::Error| AssetError::InvalidManifest(src, err.to_string()))
+    }
+
+    async fn fetch_text(&self, src: &str) -> Result<String, AssetError> {
+        self.retry(src, || async {
+            let deadline = self.deadline();
+            let response = fetch(src, deadline.as_ref()).await?;
+
+            wait(
+                response.text().map_err(fetch_failed(src))?,
+                src,
+                deadline.as_ref(),
+            )
+            .await?
+            .map_err(fetch_failed(src))?
+            .as_string()
+            .ok_or_else(|| AssetError::FetchFailed(src.to_string(), "not a string".to_string()))
+        })
+        .await
+    }
+
+    async fn retry<T, F, Fut>(&self, src: &str, mut attempt: F) -> Result<T, AssetError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, AssetError>>,
+    {
+        let mut n = 0;
+
+        loop {
+            match attempt().await {
+                Err(err) if err.is_retryable() && n + 1 < self.retry.max_attempts => {
+                    let delay = self.retry.delay(n);
 
-        let image = Rc::new(RefCell::new(Some(image)));
-        let p = create_load_promise(image.clone());
+                    log::warn!("{err}, retrying {src} in {delay:?}");
 
-        JsFuture::from(p).await.unwrap_throw();
+                    sleep(delay).await?;
+                    n += 1;
+                }
+                result => return result,
+            }
+        }
+    }
 
-        Ok(image.take().unwrap())
+    fn deadline(&self) -> Option<Deadline> {
+        self.timeout.map(|timeout| Deadline {
+            at: Date::now() + timeout.as_secs_f64() * 1000.0,
+            controller: AbortController::new().ok(),
+        })
     }
 }
 
@@ -49,10 +211,132 @@ impl Default for AssetsLoader {
     fn default() -> Self {
         Self {
             base_path: "/assets/".to_string(),
+            timeout: Some(Duration::from_secs(30)),
+            retry: RetryPolicy::default(),
         }
     }
 }
 
+/// Time limit of one attempt, the request it started is aborted when it is reached.
+struct Deadline {
+    /// `Date::now` value.
+    at: f64,
+    controller: Option<AbortController>,
+}
+
+fn fetch_failed(src: &str) -> impl Fn(JsValue) -> AssetError + '_ {
+    move |err| AssetError::FetchFailed(src.to_string(), format!("{err:?}"))
+}
+
+async fn fetch(src: &str, deadline: Option<&Deadline>) -> Result<Response, AssetError> {
+    let window = window().ok_or(InitError::NoWindow)?;
+    let init = RequestInit::new();
+
+    if let Some(controller) = deadline.and_then(|deadline| deadline.controller.as_ref()) {
+        init.set_signal(Some(&controller.signal()));
+    }
+
+    let response = wait(window.fetch_with_str_and_init(src, &init), src, deadline)
+        .await?
+        .map_err(fetch_failed(src))?
+        .unchecked_into::<Response>();
+
+    match response.status() {
+        404 | 410 => Err(AssetError::NotFound(src.to_string())),
+        status if !response.ok() => Err(AssetError::HttpStatus(src.to_string(), status)),
+        _ => Ok(response),
+    }
+}
+
+async fn decode_image(
+    src: &str,
+    blob: &Blob,
+    deadline: Option<&Deadline>,
+) -> Result<HtmlImageElement, AssetError> {
+    let document = window()
+        .ok_or(InitError::NoWindow)?
+        .document()
+        .ok_or(InitError::NoDocument)?;
+
+    let image = document
+        .create_element("img")
+        .map_err(|err| InitError::ElementCreationFailed(format!("{err:?}")))?
+        .unchecked_into::<HtmlImageElement>();
+
+    let url = Url::create_object_url_with_blob(blob)
+        .map_err(|_| AssetError::DecodeFailed(src.to_string()))?;
+
+    image.set_src(&url);
+
+    let image = Rc::new(RefCell::new(Some(image)));
+    let loaded = wait(create_load_promise(image.clone()), src, deadline).await;
+
+    // on timeout this aborts the pending load, which also detaches the listeners
+    Url::revoke_object_url(&url).ok();
+
+    loaded?.map_err(|_| AssetError::DecodeFailed(src.to_string()))?;
+
+    Ok(image.take().unwrap())
+}
+
+/// Settles `promise`, or fails with `AssetError::Timeout` once `deadline` is reached, aborting the
+/// request of the attempt so that it does not keep running in the background.
+async fn wait(
+    promise: Promise,
+    src: &str,
+    deadline: Option<&Deadline>,
+) -> Result<Result<JsValue, JsValue>, AssetError> {
+    let Some(deadline) = deadline else {
+        return Ok(JsFuture::from(promise).await);
+    };
+
+    let window = window().ok_or(InitError::NoWindow)?;
+    let timed_out: JsValue = Object::new().into();
+    let mut handle = None;
+    let timer = Promise::new(&mut |resolve, _| {
+        handle = window
+            .set_timeout_with_callback_and_timeout_and_arguments_1(
+                &resolve,
+                (deadline.at - Date::now()).max(0.0) as i32,
+                &timed_out,
+            )
+            .ok();
+    });
+
+    let result = JsFuture::from(Promise::race(&Array::of2(&promise, &timer))).await;
+
+    if let Some(handle) = handle {
+        window.clear_timeout_with_handle(handle);
+    }
+
+    match result {
+        Ok(value) if value == timed_out => {
+            if let Some(controller) = &deadline.controller {
+                controller.abort();
+            }
+
+            Err(AssetError::Timeout(src.to_string()))
+        }
+        result => Ok(result),
+    }
+}
+
+async fn sleep(duration: Duration) -> Result<(), AssetError> {
+    let window = window().ok_or(InitError::NoWindow)?;
+    let promise = Promise::new(&mut |resolve, _| {
+        window
+            .set_timeout_with_callback_and_timeout_and_arguments_0(
+                &resolve,
+                duration.as_millis() as i32,
+            )
+            .ok();
+    });
+
+    JsFuture::from(promise).await.ok();
+
+    Ok(())
+}
+
 fn create_load_promise(image: Rc<RefCell<Option<HtmlImageElement>>>) -> Promise {
     Promise::new(&mut move |resolve, reject| {
         let load_cb_ref = Rc::new(RefCell::new(None));
@@ -128,3 +412,44 @@ fn add_event_listener_with_callback(
         .add_event_listener_with_callback(event_type, cb.borrow().as_ref().unwrap())
         .unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay() {
+        let retry = RetryPolicy {
+            max_attempts: 5,
+            initial_delay: Duration::from_millis(100),
+            multiplier: 3.0,
+            max_delay: Duration::from_secs(1),
+        };
+
+        assert_eq!(retry.delay(0), Duration::from_millis(100));
+        assert_eq!(retry.delay(1), Duration::from_millis(300));
+        assert_eq!(retry.delay(2), Duration::from_millis(900));
+        assert_eq!(retry.delay(3), Duration::from_secs(1));
+
+        for multiplier in [-2.0, 0.5, f64::NAN] {
+            let retry = RetryPolicy {
+                multiplier,
+                ..retry
+            };
+
+            assert_eq!(retry.delay(1), Duration::from_millis(100));
+            assert_eq!(retry.delay(2), Duration::from_millis(100));
+        }
+    }
+
+    #[test]
+    fn test_defaults() {
+        let loader = AssetsLoader::new("/cdn/").with_retry(RetryPolicy::with_attempts(3));
+
+        assert_eq!(loader.base_path, "/cdn/");
+        assert_eq!(loader.timeout, Some(Duration::from_secs(30)));
+        assert_eq!(loader.retry.max_attempts, 3);
+        assert_eq!(RetryPolicy::none().max_attempts, 1);
+        assert_eq!(loader.with_timeout(None).timeout, None);
+    }
+}
@@ -0,0 +1,189 @@
+use std::f64::consts::PI;
+
+use wasm_bindgen::{Clamped, JsCast, UnwrapThrowExt};
+use web_sys::{window, CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement, ImageData};
+
+use super::{
+    super::raster::{RasterVertex, Rasterizer},
+    Color, Rect, RenderBackend, Transform2d,
+};
+
+pub struct CanvasBackend {
+    context: CanvasRenderingContext2d,
+    width: f64,
+    height: f64,
+    rasterizer: Option<Rasterizer>,
+    /// Detached canvas the rasterized triangles are uploaded to, to draw them over the canvas
+    /// rather than replace its pixels.
+    layer: CanvasRenderingContext2d,
+    /// Whether the rasterizer holds triangles not copied to the canvas yet.
+    dirty: bool,
+}
+
+impl CanvasBackend {
+    pub fn new(context: CanvasRenderingContext2d, width: u32, height: u32) -> Self {
+        Self {
+            context,
+            width: width as f64,
+            height: height as f64,
+            rasterizer: Some(Rasterizer::new(width as usize, height as usize)),
+            layer: create_layer(width, height),
+            dirty: false,
+        }
+    }
+
+    /// Triangles go through the software rasterizer when enabled, otherwise they are painted
+    /// directly on the canvas in the order they are drawn.
+    pub fn set_depth_buffer(&mut self, enabled: bool) {
+        self.rasterizer =
+            enabled.then(|| Rasterizer::new(self.width as usize, self.height as usize));
+    }
+
+    pub fn depth_buffer(&self) -> bool {
+        self.rasterizer.is_some()
+    }
+
+    /// Draws the triangles rasterized since the last flush over the canvas, so that what is drawn
+    /// directly on the canvas next ends up on top of them and what was drawn before stays where
+    /// they leave it uncovered. The depths are kept for the triangles filled next.
+    fn flush(&mut self) {
+        let Some(rasterizer) = self.rasterizer.as_mut().filter(|_| self.dirty) else {
+            return;
+        };
+
+        let image = ImageData::new_with_u8_clamped_array_and_sh(
+            Clamped(rasterizer.color_buffer()),
+            rasterizer.width() as u32,
+            rasterizer.height() as u32,
+        )
+        .unwrap_throw();
+
+        self.layer.put_image_data(&image, 0.0, 0.0).unwrap_throw();
+        self.context
+            .draw_image_with_html_canvas_element(&self.layer.canvas().unwrap_throw(), 0.0, 0.0)
+            .unwrap_throw();
+        rasterizer.clear_color([0; 4]);
+        self.dirty = false;
+    }
+}
+
+impl RenderBackend for CanvasBackend {
+    type Image = HtmlImageElement;
+
+    fn width(&self) -> f64 {
+        self.width
+    }
+
+    fn height(&self) -> f64 {
+        self.height
+    }
+
+    fn clear(&mut self, color: Color) {
+        self.context.set_fill_style_str(&color.to_css());
+        self.context.fill_rect(0.0, 0.0, self.width, self.height);
+
+        // transparent, the canvas shows through what no triangle covers
+        if let Some(rasterizer) = self.rasterizer.as_mut() {
+            rasterizer.clear([0; 4]);
+        }
+
+        self.dirty = false;
+    }
+
+    fn draw_point(&mut self, x: f64, y: f64, radius: f64, color: Color) {
+        self.flush();
+        self.context.set_fill_style_str(&color.to_css());
+        self.context.begin_path();
+        self.context.arc(x, y, radius, 0.0, PI * 2.0).unwrap_throw();
+        self.context.fill();
+        self.context.close_path();
+    }
+
+    fn draw_line(&mut self, (x1, y1): (f64, f64), (x2, y2): (f64, f64), color: Color) {
+        self.flush();
+        self.context.set_stroke_style_str(&color.to_css());
+        self.context.begin_path();
+        self.context.move_to(x1, y1);
+        self.context.line_to(x2, y2);
+        self.context.stroke();
+    }
+
+    fn fill_triangle(&mut self, vertices: &[RasterVertex; 3]) {
+        if let Some(rasterizer) = self.rasterizer.as_mut() {
+            rasterizer.fill_triangle(vertices);
+            self.dirty = true;
+            return;
+        }
+
+        let [a, b, c] = vertices;
+        let style = Color::rgb(a.color).to_css();
+
+        // stroking with the same color hides the seams between adjacent triangles
+        self.context.set_fill_style_str(&style);
+        self.context.set_stroke_style_str(&style);
+        self.context.begin_path();
+        self.context.move_to(a.x, a.y);
+        self.context.line_to(b.x, b.y);
+        self.context.line_to(c.x, c.y);
+        self.context.close_path();
+        self.context.fill();
+        self.context.stroke();
+    }
+
+    fn draw_image(
+        &mut self,
+        image: &HtmlImageElement,
+        source: Rect,
+        destination: Rect,
+        transform: &Transform2d,
+        alpha: f64,
+    ) {
+        let [a, b, c, d, e, f] = transform.0;
+
+        self.flush();
+        self.context.save();
+        self.context.set_global_alpha(alpha);
+        self.context.set_transform(a, b, c, d, e, f).unwrap_throw();
+        self.context
+            .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                image,
+                source.x,
+                source.y,
+                source.width,
+                source.height,
+                destination.x,
+                destination.y,
+                destination.width,
+                destination.height,
+            )
+            .unwrap_throw();
+        self.context.restore();
+    }
+
+    fn text(&mut self, text: &str, x: f64, y: f64, color: Color) {
+        self.flush();
+        self.context.set_fill_style_str(&color.to_css());
+        self.context.fill_text(text, x, y).unwrap_throw();
+    }
+
+    fn present(&mut self) {
+        self.flush();
+    }
+}
+
+fn create_layer(width: u32, height: u32) -> CanvasRenderingContext2d {
+    let canvas = window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.create_element("canvas").ok())
+        .unwrap_throw()
+        .unchecked_into::<HtmlCanvasElement>();
+
+    canvas.set_width(width);
+    canvas.set_height(height);
+    canvas
+        .get_context("2d")
+        .ok()
+        .flatten()
+        .unwrap_throw()
+        .unchecked_into()
+}
@@ -0,0 +1,246 @@
+use super::{
+    super::raster::{RasterVertex, Rasterizer},
+    Color, Rect, RenderBackend, Transform2d,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryImage {
+    pub name: String,
+    pub width: f64,
+    pub height: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DrawCommand {
+    Clear(Color),
+    Point {
+        x: f64,
+        y: f64,
+        radius: f64,
+        color: Color,
+    },
+    Line {
+        from: (f64, f64),
+        to: (f64, f64),
+        color: Color,
+    },
+    Triangle([RasterVertex; 3]),
+    Image {
+        name: String,
+        source: Rect,
+        destination: Rect,
+        transform: Transform2d,
+        alpha: f64,
+    },
+    Text {
+        text: String,
+        x: f64,
+        y: f64,
+        color: Color,
+    },
+    Present,
+}
+
+/// Records every command and rasterizes triangles in memory, for tests. Like `CanvasBackend`,
+/// the triangles are composited onto the frame before anything else is drawn, lines and points
+/// are plotted on the frame directly.
+#[derive(Debug, Clone)]
+pub struct MemoryBackend {
+    pub commands: Vec<DrawCommand>,
+    pub rasterizer: Rasterizer,
+    /// RGBA bytes of what the canvas would show, row by row.
+    frame: Vec<u8>,
+    /// Whether the rasterizer holds triangles not composited onto the frame yet.
+    dirty: bool,
+}
+
+impl MemoryBackend {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            commands: vec![],
+            rasterizer: Rasterizer::new(width, height),
+            frame: vec![0; width * height * 4],
+            dirty: false,
+        }
+    }
+
+    /// Pixel of the frame, as composited so far.
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        let i = (y * self.rasterizer.width() + x) * 4;
+
+        [
+            self.frame[i],
+            self.frame[i + 1],
+            self.frame[i + 2],
+            self.frame[i + 3],
+        ]
+    }
+
+    pub fn triangles(&self) -> impl Iterator<Item = &[RasterVertex; 3]> {
+        self.commands.iter().filter_map(|command| match command {
+            DrawCommand::Triangle(vertices) => Some(vertices),
+            _ => None,
+        })
+    }
+
+    /// Draws the triangles rasterized since the last flush over the frame, keeping the pixels
+    /// they do not cover.
+    fn flush(&mut self) {
+        if !self.dirty {
+            return;
+        }
+
+        let layer = self.rasterizer.color_buffer().chunks_exact(4);
+
+        for (pixel, color) in self.frame.chunks_exact_mut(4).zip(layer) {
+            if color[3] != 0 {
+                pixel.copy_from_slice(color);
+            }
+        }
+
+        self.rasterizer.clear_color([0; 4]);
+        self.dirty = false;
+    }
+
+    fn plot(&mut self, x: f64, y: f64, color: Color) {
+        let (width, height) = (self.rasterizer.width(), self.rasterizer.height());
+
+        if x < 0.0 || y < 0.0 || x >= width as f64 || y >= height as f64 {
+            return;
+        }
+
+        let i = (y as usize * width + x as usize) * 4;
+
+        self.frame[i..i + 4].copy_from_slice(&color.to_rgba8());
+    }
+}
+
+impl RenderBackend for MemoryBackend {
+    type Image = MemoryImage;
+
+    fn width(&self) -> f64 {
+        self.rasterizer.width() as f64
+    }
+
+    fn height(&self) -> f64 {
+        self.rasterizer.height() as f64
+    }
+
+    fn clear(&mut self, color: Color) {
+        for pixel in self.frame.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&color.to_rgba8());
+        }
+
+        self.rasterizer.clear([0; 4]);
+        self.dirty = false;
+        self.commands.push(DrawCommand::Clear(color));
+    }
+
+    fn draw_point(&mut self, x: f64, y: f64, radius: f64, color: Color) {
+        self.flush();
+        self.plot(x, y, color);
+        self.commands.push(DrawCommand::Point {
+            x,
+            y,
+            radius,
+            color,
+        });
+    }
+
+    fn draw_line(&mut self, from: (f64, f64), to: (f64, f64), color: Color) {
+        let steps = (to.0 - from.0)
+            .abs()
+            .max((to.1 - from.1).abs())
+            .ceil()
+            .max(1.0);
+
+        self.flush();
+
+        for step in 0..=steps as usize {
+            let t = step as f64 / steps;
+
+            self.plot(
+                from.0 + (to.0 - from.0) * t,
+                from.1 + (to.1 - from.1) * t,
+                color,
+            );
+        }
+
+        self.commands.push(DrawCommand::Line { from, to, color });
+    }
+
+    fn fill_triangle(&mut self, vertices: &[RasterVertex; 3]) {
+        self.rasterizer.fill_triangle(vertices);
+        self.dirty = true;
+        self.commands.push(DrawCommand::Triangle(*vertices));
+    }
+
+    fn draw_image(
+        &mut self,
+        image: &MemoryImage,
+        source: Rect,
+        destination: Rect,
+        transform: &Transform2d,
+        alpha: f64,
+    ) {
+        self.flush();
+        self.commands.push(DrawCommand::Image {
+            name: image.name.clone(),
+            source,
+            destination,
+            transform: *transform,
+            alpha,
+        });
+    }
+
+    fn text(&mut self, text: &str, x: f64, y: f64, color: Color) {
+        self.flush();
+        self.commands.push(DrawCommand::Text {
+            text: text.to_string(),
+            x,
+            y,
+            color,
+        });
+    }
+
+    fn present(&mut self) {
+        self.flush();
+        self.commands.push(DrawCommand::Present);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle(x: f64, z: f64, color: [f64; 3]) -> [RasterVertex; 3] {
+        [(x, 0.0), (x + 4.0, 0.0), (x, 4.0)].map(|(x, y)| RasterVertex {
+            x,
+            y,
+            z,
+            w: 1.0,
+            color,
+        })
+    }
+
+    #[test]
+    fn test_line_between_fill_batches() {
+        let mut backend = MemoryBackend::new(8, 4);
+        let red = [1.0, 0.0, 0.0];
+
+        backend.clear(Color::BLACK);
+        backend.fill_triangle(&triangle(0.0, 0.5, red));
+        backend.draw_line((0.0, 3.5), (7.0, 3.5), Color::WHITE);
+        // further right, then behind the first batch
+        backend.fill_triangle(&triangle(4.0, 0.5, red));
+        backend.fill_triangle(&triangle(0.0, 0.75, [0.0, 0.0, 1.0]));
+        backend.present();
+
+        assert_eq!(backend.pixel(0, 0), [255, 0, 0, 255]);
+        assert_eq!(backend.pixel(4, 0), [255, 0, 0, 255]);
+        assert_eq!(backend.pixel(7, 2), [0, 0, 0, 255]);
+        // the line drawn over the first batch is not erased by the second one
+        assert_eq!(backend.pixel(0, 3), [255, 255, 255, 255]);
+        assert_eq!(backend.pixel(7, 3), [255, 255, 255, 255]);
+    }
+}
@@ -0,0 +1,167 @@
+pub use canvas::CanvasBackend;
+pub use memory::{DrawCommand, MemoryBackend, MemoryImage};
+
+mod canvas;
+mod memory;
+
+use std::ops::Mul;
+
+use super::raster::RasterVertex;
+
+/// RGBA color with channels in `[0, 1]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color(pub [f64; 4]);
+
+impl Color {
+    pub const BLACK: Color = Color([0.0, 0.0, 0.0, 1.0]);
+    pub const WHITE: Color = Color([1.0, 1.0, 1.0, 1.0]);
+    pub const PINK: Color = Color([1.0, 0.75, 0.8, 1.0]);
+
+    pub fn rgb([r, g, b]: [f64; 3]) -> Self {
+        Self([r, g, b, 1.0])
+    }
+
+    pub fn to_rgba8(&self) -> [u8; 4] {
+        self.0.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+    }
+
+    pub fn to_css(&self) -> String {
+        let [r, g, b, _] = self.to_rgba8();
+
+        format!("rgba({}, {}, {}, {})", r, g, b, self.0[3])
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+/// 2d affine transform in the canvas `setTransform(a, b, c, d, e, f)` layout, mapping `(x, y)` to
+/// `(a * x + c * y + e, b * x + d * y + f)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform2d(pub [f64; 6]);
+
+impl Transform2d {
+    pub const IDENTITY: Transform2d = Transform2d([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+    pub fn translation(x: f64, y: f64) -> Self {
+        Self([1.0, 0.0, 0.0, 1.0, x, y])
+    }
+
+    /// Clockwise on the canvas, where y points down.
+    pub fn rotation(angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+
+        Self([cos, sin, -sin, cos, 0.0, 0.0])
+    }
+
+    pub fn scale(x: f64, y: f64) -> Self {
+        Self([x, 0.0, 0.0, y, 0.0, 0.0])
+    }
+
+    pub fn apply(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        let [a, b, c, d, e, f] = self.0;
+
+        (a * x + c * y + e, b * x + d * y + f)
+    }
+}
+
+impl Default for Transform2d {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// `self * rhs` applies `rhs` first.
+impl Mul for Transform2d {
+    type Output = Transform2d;
+
+    fn mul(self, rhs: Transform2d) -> Self::Output {
+        let [a1, b1, c1, d1, e1, f1] = self.0;
+        let [a2, b2, c2, d2, e2, f2] = rhs.0;
+
+        Transform2d([
+            a1 * a2 + c1 * b2,
+            b1 * a2 + d1 * b2,
+            a1 * c2 + c1 * d2,
+            b1 * c2 + d1 * d2,
+            a1 * e2 + c1 * f2 + e1,
+            b1 * e2 + d1 * f2 + f1,
+        ])
+    }
+}
+
+/// Drawing primitives in canvas coordinates, `Renderer` does the projection.
+pub trait RenderBackend {
+    type Image;
+
+    fn width(&self) -> f64;
+
+    fn height(&self) -> f64;
+
+    fn clear(&mut self, color: Color);
+
+    fn draw_point(&mut self, x: f64, y: f64, radius: f64, color: Color);
+
+    fn draw_line(&mut self, from: (f64, f64), to: (f64, f64), color: Color);
+
+    /// Triangles are depth tested when the backend has a depth buffer.
+    fn fill_triangle(&mut self, vertices: &[RasterVertex; 3]);
+
+    /// Draws the `source` region of `image` into `destination`, both transformed by `transform`.
+    fn draw_image(
+        &mut self,
+        image: &Self::Image,
+        source: Rect,
+        destination: Rect,
+        transform: &Transform2d,
+        alpha: f64,
+    );
+
+    fn text(&mut self, text: &str, x: f64, y: f64, color: Color);
+
+    /// Called once at the end of each frame.
+    fn present(&mut self);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use super::*;
+
+    fn assert_point_approx_eq((x1, y1): (f64, f64), (x2, y2): (f64, f64)) {
+        assert!(
+            (x1 - x2).abs() < 1e-9 && (y1 - y2).abs() < 1e-9,
+            "{:?} != {:?}",
+            (x1, y1),
+            (x2, y2)
+        );
+    }
+
+    #[test]
+    fn test_transform_2d() {
+        let transform = Transform2d::translation(10.0, 0.0)
+            * Transform2d::rotation(FRAC_PI_2)
+            * Transform2d::scale(2.0, 1.0);
+
+        assert_point_approx_eq(transform.apply((1.0, 0.0)), (10.0, 2.0));
+        assert_point_approx_eq(transform.apply((0.0, 1.0)), (9.0, 0.0));
+        assert_eq!(Transform2d::IDENTITY.apply((3.0, 4.0)), (3.0, 4.0));
+    }
+}
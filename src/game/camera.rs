@@ -0,0 +1,352 @@
+use std::f64::consts::{FRAC_PI_2, PI};
+
+use super::math::{look_at, new_orthographic, new_perspective, Mat4, Quat, Vec3};
+
+const MAX_PITCH: f64 = FRAC_PI_2 - 0.01;
+
+/// Closest the orbit camera gets to its target.
+const MIN_DISTANCE: f64 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    pub position: Vec3,
+    pub orientation: Quat,
+    pub fov: f64,
+    pub z_near: f64,
+    pub z_far: f64,
+    pub projection_mode: ProjectionMode,
+    /// Half of the visible height in world units when using an orthographic projection.
+    pub ortho_size: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProjectionMode {
+    #[default]
+    Perspective,
+    Orthographic,
+}
+
+impl Camera {
+    pub fn forward(&self) -> Vec3 {
+        self.orientation.rotate(Vec3::new(0.0, 0.0, 1.0))
+    }
+
+    pub fn right(&self) -> Vec3 {
+        self.orientation.rotate(Vec3::new(1.0, 0.0, 0.0))
+    }
+
+    pub fn up(&self) -> Vec3 {
+        self.orientation.rotate(Vec3::new(0.0, 1.0, 0.0))
+    }
+
+    /// Points the camera at `target` without rolling it.
+    pub fn look_at(&mut self, target: Vec3) {
+        let (yaw, pitch) = yaw_pitch(target - self.position);
+
+        self.orientation = orientation(yaw, pitch);
+    }
+
+    pub fn view(&self) -> Mat4 {
+        look_at(self.position, self.position + self.forward(), self.up())
+    }
+
+    pub fn projection(&self, width: f64, height: f64) -> Mat4 {
+        match self.projection_mode {
+            ProjectionMode::Perspective => {
+                new_perspective(width, height, self.fov, self.z_near, self.z_far)
+            }
+            ProjectionMode::Orthographic => {
+                let top = self.ortho_size;
+                let right = top * width / height;
+
+                new_orthographic(-right, right, -top, top, self.z_near, self.z_far)
+            }
+        }
+    }
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            position: Vec3::new(0.0, 0.0, -3.0),
+            orientation: Quat::identity(),
+            fov: FRAC_PI_2,
+            z_near: 0.1,
+            z_far: 1000.0,
+            projection_mode: ProjectionMode::default(),
+            ortho_size: 1.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CameraMode {
+    #[default]
+    Orbit,
+    FreeFly,
+}
+
+/// Input accumulated since the last update, `look` is in pixels and `movement` is `[right, up, forward]`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CameraInput {
+    pub look: (f64, f64),
+    pub movement: Vec3,
+    pub zoom: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct OrbitController {
+    pub target: Vec3,
+    pub distance: f64,
+    pub yaw: f64,
+    pub pitch: f64,
+    pub sensitivity: f64,
+}
+
+impl OrbitController {
+    pub fn update(&mut self, camera: &mut Camera, input: &CameraInput, delta: f64) {
+        self.yaw += input.look.0 * self.sensitivity;
+        self.pitch = (self.pitch + input.look.1 * self.sensitivity).clamp(-MAX_PITCH, MAX_PITCH);
+        self.distance =
+            (self.distance - (input.zoom + input.movement.z()) * delta).max(MIN_DISTANCE);
+
+        let orientation = orientation(self.yaw, self.pitch);
+
+        camera.orientation = orientation;
+        camera.position = self.target - orientation.rotate(Vec3::new(0.0, 0.0, self.distance));
+    }
+}
+
+impl Default for OrbitController {
+    fn default() -> Self {
+        Self {
+            target: Vec3::default(),
+            distance: 3.0,
+            yaw: 0.0,
+            pitch: 0.0,
+            sensitivity: PI / 500.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FreeFlyController {
+    pub yaw: f64,
+    pub pitch: f64,
+    pub speed: f64,
+    pub sensitivity: f64,
+}
+
+impl FreeFlyController {
+    pub fn update(&mut self, camera: &mut Camera, input: &CameraInput, delta: f64) {
+        self.yaw += input.look.0 * self.sensitivity;
+        self.pitch = (self.pitch + input.look.1 * self.sensitivity).clamp(-MAX_PITCH, MAX_PITCH);
+
+        camera.orientation = orientation(self.yaw, self.pitch);
+
+        let m = input.movement;
+        let direction = camera.right() * m.x() + camera.up() * m.y() + camera.forward() * m.z();
+
+        if direction.length() > 0.0 {
+            camera.position = camera.position + direction.normalize() * self.speed * delta;
+        }
+    }
+}
+
+impl Default for FreeFlyController {
+    fn default() -> Self {
+        Self {
+            yaw: 0.0,
+            pitch: 0.0,
+            speed: 2.0,
+            sensitivity: PI / 500.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum CameraController {
+    Orbit(OrbitController),
+    FreeFly(FreeFlyController),
+}
+
+impl CameraController {
+    /// Switches controller while keeping the camera where it is and where it looks. The orbit
+    /// target is the point ahead of the camera closest to the default target, or the default
+    /// distance ahead when that target is behind the camera.
+    pub fn new(mode: CameraMode, camera: &Camera) -> Self {
+        let (yaw, pitch) = yaw_pitch(camera.forward());
+
+        match mode {
+            CameraMode::Orbit => {
+                let orbit = OrbitController::default();
+                let forward = orientation(yaw, pitch).rotate(Vec3::new(0.0, 0.0, 1.0));
+                let ahead = (orbit.target - camera.position).dot(&forward);
+                let distance = if ahead >= MIN_DISTANCE {
+                    ahead
+                } else {
+                    orbit.distance
+                };
+
+                CameraController::Orbit(OrbitController {
+                    target: camera.position + forward * distance,
+                    distance,
+                    yaw,
+                    pitch,
+                    ..orbit
+                })
+            }
+            CameraMode::FreeFly => CameraController::FreeFly(FreeFlyController {
+                yaw,
+                pitch,
+                ..Default::default()
+            }),
+        }
+    }
+
+    pub fn mode(&self) -> CameraMode {
+        match self {
+            CameraController::Orbit(_) => CameraMode::Orbit,
+            CameraController::FreeFly(_) => CameraMode::FreeFly,
+        }
+    }
+
+    /// `delta` is in seconds.
+    pub fn update(&mut self, camera: &mut Camera, input: &CameraInput, delta: f64) {
+        match self {
+            CameraController::Orbit(controller) => controller.update(camera, input, delta),
+            CameraController::FreeFly(controller) => controller.update(camera, input, delta),
+        }
+    }
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        CameraController::Orbit(OrbitController::default())
+    }
+}
+
+fn yaw_pitch(direction: Vec3) -> (f64, f64) {
+    let horizontal = (direction.x().powi(2) + direction.z().powi(2)).sqrt();
+
+    (
+        direction.x().atan2(direction.z()),
+        (-direction.y()).atan2(horizontal),
+    )
+}
+
+fn orientation(yaw: f64, pitch: f64) -> Quat {
+    Quat::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), yaw)
+        * Quat::from_axis_angle(Vec3::new(1.0, 0.0, 0.0), pitch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::math::{new_translation, Vec4};
+    use super::*;
+
+    fn assert_vec_approx_eq(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-9, "expected {b:?}, got {a:?}");
+    }
+
+    #[test]
+    fn test_default_view() {
+        let view = Camera::default().view();
+
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!(
+                    (view.value[i][j] - new_translation(0.0, 0.0, 3.0).value[i][j]).abs() < 1e-9
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_orthographic_projection() {
+        let camera = Camera {
+            projection_mode: ProjectionMode::Orthographic,
+            ortho_size: 2.0,
+            ..Default::default()
+        };
+        let r = camera.projection(800.0, 400.0) * Vec4::new(4.0, -2.0, 0.1, 1.0);
+
+        assert_eq!(r.x(), 1.0);
+        assert_eq!(r.y(), -1.0);
+        assert_eq!(r.w(), 1.0);
+    }
+
+    #[test]
+    fn test_look_at() {
+        let mut camera = Camera {
+            position: Vec3::new(3.0, 3.0, 0.0),
+            ..Default::default()
+        };
+
+        camera.look_at(Vec3::default());
+
+        assert_vec_approx_eq(camera.forward(), Vec3::new(-1.0, -1.0, 0.0).normalize());
+    }
+
+    #[test]
+    fn test_orbit() {
+        let mut camera = Camera::default();
+        let mut controller = CameraController::new(CameraMode::Orbit, &camera);
+        let input = CameraInput {
+            look: (250.0, 0.0),
+            ..Default::default()
+        };
+
+        controller.update(&mut camera, &input, 0.016);
+
+        // a quarter turn around the origin
+        assert_vec_approx_eq(camera.position, Vec3::new(-3.0, 0.0, 0.0));
+        assert_vec_approx_eq(camera.forward(), Vec3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_switch_to_orbit_keeps_camera() {
+        // flown off to the side, looking past the origin
+        let mut camera = Camera {
+            position: Vec3::new(1.0, 0.0, -3.0),
+            ..Default::default()
+        };
+        let mut controller = CameraController::new(CameraMode::Orbit, &camera);
+
+        controller.update(&mut camera, &CameraInput::default(), 0.016);
+
+        assert_vec_approx_eq(camera.position, Vec3::new(1.0, 0.0, -3.0));
+        assert_vec_approx_eq(camera.forward(), Vec3::new(0.0, 0.0, 1.0));
+
+        let CameraController::Orbit(orbit) = controller else {
+            panic!("expected an orbit controller");
+        };
+
+        assert_vec_approx_eq(orbit.target, Vec3::new(1.0, 0.0, 0.0));
+
+        // facing away from the origin
+        camera.look_at(Vec3::new(1.0, 0.0, -10.0));
+
+        let mut controller = CameraController::new(CameraMode::Orbit, &camera);
+        let position = camera.position;
+
+        controller.update(&mut camera, &CameraInput::default(), 0.016);
+
+        assert_vec_approx_eq(camera.position, position);
+    }
+
+    #[test]
+    fn test_free_fly() {
+        let mut camera = Camera::default();
+        let mut controller = CameraController::new(CameraMode::FreeFly, &camera);
+        let input = CameraInput {
+            movement: Vec3::new(0.0, 0.0, 1.0),
+            ..Default::default()
+        };
+
+        controller.update(&mut camera, &input, 0.5);
+
+        assert_eq!(controller.mode(), CameraMode::FreeFly);
+        assert_vec_approx_eq(camera.position, Vec3::new(0.0, 0.0, -2.0));
+    }
+}
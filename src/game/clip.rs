@@ -0,0 +1,298 @@
+use super::math::{Mat4, Vec3, Vec4};
+
+/// Planes of the clip space volume `-w <= x <= w`, `-w <= y <= w`, `0 <= z <= w`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipPlane {
+    Near,
+    Far,
+    Left,
+    Right,
+    Bottom,
+    Top,
+}
+
+impl ClipPlane {
+    pub const NEAR: &'static [ClipPlane] = &[ClipPlane::Near];
+    pub const ALL: &'static [ClipPlane] = &[
+        ClipPlane::Near,
+        ClipPlane::Far,
+        ClipPlane::Left,
+        ClipPlane::Right,
+        ClipPlane::Bottom,
+        ClipPlane::Top,
+    ];
+
+    /// Positive inside the plane.
+    pub fn distance(&self, v: &Vec4) -> f64 {
+        match self {
+            ClipPlane::Near => v.z(),
+            ClipPlane::Far => v.w() - v.z(),
+            ClipPlane::Left => v.w() + v.x(),
+            ClipPlane::Right => v.w() - v.x(),
+            ClipPlane::Bottom => v.w() + v.y(),
+            ClipPlane::Top => v.w() - v.y(),
+        }
+    }
+}
+
+/// Clips a segment in clip space, `None` when it is entirely outside.
+pub fn clip_line(a: Vec4, b: Vec4, planes: &[ClipPlane]) -> Option<(Vec4, Vec4)> {
+    let (mut a, mut b) = (a, b);
+
+    for plane in planes {
+        let (da, db) = (plane.distance(&a), plane.distance(&b));
+
+        match (da >= 0.0, db >= 0.0) {
+            (true, true) => {}
+            (false, false) => return None,
+            (true, false) => b = a.lerp(&b, da / (da - db)),
+            (false, true) => a = a.lerp(&b, da / (da - db)),
+        }
+    }
+
+    Some((a, b))
+}
+
+/// Sutherland-Hodgman clipping of a convex polygon in clip space, the winding is preserved.
+pub fn clip_polygon(vertices: &[Vec4], planes: &[ClipPlane]) -> Vec<Vec4> {
+    let mut output = vertices.to_vec();
+
+    for plane in planes {
+        if output.is_empty() {
+            break;
+        }
+
+        let input = std::mem::take(&mut output);
+
+        for (i, current) in input.iter().enumerate() {
+            let next = &input[(i + 1) % input.len()];
+            let (dc, dn) = (plane.distance(current), plane.distance(next));
+
+            if dc >= 0.0 {
+                output.push(*current);
+            }
+
+            if (dc >= 0.0) != (dn >= 0.0) {
+                output.push(current.lerp(next, dc / (dc - dn)));
+            }
+        }
+    }
+
+    output
+}
+
+/// Clips a triangle and fans the result back into triangles.
+pub fn clip_triangle(triangle: &[Vec4; 3], planes: &[ClipPlane]) -> Vec<[Vec4; 3]> {
+    if planes
+        .iter()
+        .all(|plane| triangle.iter().all(|v| plane.distance(v) >= 0.0))
+    {
+        return vec![*triangle];
+    }
+
+    let polygon = clip_polygon(triangle, planes);
+
+    (1..polygon.len().saturating_sub(1))
+        .map(|i| [polygon[0], polygon[i], polygon[i + 1]])
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Vec3,
+    pub radius: f64,
+}
+
+impl BoundingSphere {
+    /// Sphere around the axis aligned bounding box of `points`.
+    pub fn from_points(points: &[Vec3]) -> Self {
+        let Some(first) = points.first() else {
+            return Self::default();
+        };
+
+        let (min, max) = points.iter().fold((*first, *first), |(min, max), p| {
+            (
+                Vec3::new(min.x().min(p.x()), min.y().min(p.y()), min.z().min(p.z())),
+                Vec3::new(max.x().max(p.x()), max.y().max(p.y()), max.z().max(p.z())),
+            )
+        });
+
+        let center = (min + max) * 0.5;
+        let radius = points
+            .iter()
+            .map(|p| (*p - center).length())
+            .fold(0.0, f64::max);
+
+        Self { center, radius }
+    }
+
+    /// Bounding sphere after `transform`, the radius grows with the largest axis scale.
+    pub fn transform(&self, transform: &Mat4) -> Self {
+        let m = &transform.value;
+        let scale = (0..3)
+            .map(|j| Vec3::new(m[0][j], m[1][j], m[2][j]).length())
+            .fold(0.0, f64::max);
+
+        Self {
+            center: Vec3::from(*transform * Vec4::from(self.center)),
+            radius: self.radius * scale,
+        }
+    }
+}
+
+/// World space frustum planes extracted from a view-projection matrix, as `(normal, d)` with
+/// `normal . p + d >= 0` inside.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    planes: [(Vec3, f64); 6],
+}
+
+impl Frustum {
+    pub fn from_view_projection(m: &Mat4) -> Self {
+        let row = |i: usize| Vec4::from(m.value[i]);
+        let plane = |v: Vec4| {
+            let normal = Vec3::new(v.x(), v.y(), v.z());
+            let length = normal.length();
+
+            (normal / length, v.w() / length)
+        };
+
+        Self {
+            planes: [
+                plane(row(2)),
+                plane(row(3) - row(2)),
+                plane(row(3) + row(0)),
+                plane(row(3) - row(0)),
+                plane(row(3) + row(1)),
+                plane(row(3) - row(1)),
+            ],
+        }
+    }
+
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes
+            .iter()
+            .all(|(normal, d)| normal.dot(&sphere.center) + d >= -sphere.radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{camera::Camera, math::new_translation};
+    use super::*;
+
+    fn assert_inside(v: &Vec4, planes: &[ClipPlane]) {
+        for plane in planes {
+            assert!(plane.distance(v) >= -1e-9, "{v:?} outside {plane:?}");
+        }
+    }
+
+    #[test]
+    fn test_clip_line() {
+        let a = Vec4::new(0.0, 0.0, -1.0, 1.0);
+        let b = Vec4::new(0.0, 0.0, 1.0, 1.0);
+        let (a, b) = clip_line(a, b, ClipPlane::NEAR).unwrap();
+
+        assert_eq!(a, Vec4::new(0.0, 0.0, 0.0, 1.0));
+        assert_eq!(b, Vec4::new(0.0, 0.0, 1.0, 1.0));
+
+        assert_eq!(
+            clip_line(
+                Vec4::new(0.0, 0.0, -2.0, 1.0),
+                Vec4::new(0.0, 0.0, -1.0, 1.0),
+                ClipPlane::NEAR
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_clip_triangle_inside() {
+        let triangle = [
+            Vec4::new(0.0, 0.0, 0.5, 1.0),
+            Vec4::new(0.5, 0.0, 0.5, 1.0),
+            Vec4::new(0.0, 0.5, 0.5, 1.0),
+        ];
+
+        assert_eq!(clip_triangle(&triangle, ClipPlane::ALL), vec![triangle]);
+    }
+
+    #[test]
+    fn test_clip_triangle_near() {
+        // one vertex behind the camera turns the triangle into a quad
+        let triangle = [
+            Vec4::new(0.0, 0.0, -1.0, 0.5),
+            Vec4::new(1.0, 0.0, 1.0, 2.0),
+            Vec4::new(0.0, 1.0, 1.0, 2.0),
+        ];
+        let clipped = clip_triangle(&triangle, ClipPlane::NEAR);
+
+        assert_eq!(clipped.len(), 2);
+
+        for v in clipped.iter().flatten() {
+            assert_inside(v, ClipPlane::NEAR);
+            assert!(v.w() > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_clip_triangle_outside() {
+        let triangle = [
+            Vec4::new(0.0, 0.0, -1.0, 1.0),
+            Vec4::new(1.0, 0.0, -1.0, 1.0),
+            Vec4::new(0.0, 1.0, -1.0, 1.0),
+        ];
+
+        assert!(clip_triangle(&triangle, ClipPlane::ALL).is_empty());
+    }
+
+    #[test]
+    fn test_clip_polygon_all_planes() {
+        let triangle = [
+            Vec4::new(-3.0, -3.0, 0.5, 1.0),
+            Vec4::new(3.0, -3.0, 0.5, 1.0),
+            Vec4::new(0.0, 3.0, 0.5, 1.0),
+        ];
+        let polygon = clip_polygon(&triangle, ClipPlane::ALL);
+
+        assert!(polygon.len() >= 4);
+
+        for v in &polygon {
+            assert_inside(v, ClipPlane::ALL);
+        }
+    }
+
+    #[test]
+    fn test_bounding_sphere() {
+        let sphere = BoundingSphere::from_points(&[
+            Vec3::new(-1.0, 0.0, 0.0),
+            Vec3::new(3.0, 0.0, 0.0),
+            Vec3::new(1.0, 1.0, 0.0),
+        ]);
+
+        assert_eq!(sphere.center, Vec3::new(1.0, 0.5, 0.0));
+        assert!((sphere.radius - 4.25_f64.sqrt()).abs() < 1e-9);
+
+        let moved = sphere.transform(&new_translation(1.0, 2.0, 3.0));
+
+        assert_eq!(moved.center, Vec3::new(2.0, 2.5, 3.0));
+        assert_eq!(moved.radius, sphere.radius);
+    }
+
+    #[test]
+    fn test_frustum_culling() {
+        let camera = Camera::default();
+        let frustum =
+            Frustum::from_view_projection(&(camera.projection(480.0, 360.0) * camera.view()));
+        let sphere = |x: f64, y: f64, z: f64| BoundingSphere {
+            center: Vec3::new(x, y, z),
+            radius: 1.0,
+        };
+
+        assert!(frustum.intersects_sphere(&sphere(0.0, 0.0, 0.0)));
+        assert!(frustum.intersects_sphere(&sphere(0.0, 0.0, -3.5)));
+        assert!(!frustum.intersects_sphere(&sphere(0.0, 0.0, -5.0)));
+        assert!(!frustum.intersects_sphere(&sphere(50.0, 0.0, 0.0)));
+        assert!(!frustum.intersects_sphere(&sphere(0.0, 0.0, 2000.0)));
+    }
+}
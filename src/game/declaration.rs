@@ -6,6 +6,14 @@ const GAME_OPTIONS: &'static str = r#"
 export interface GameOptions {
     devMode?: boolean;
     container?: HTMLElement;
+    assetsPath?: string;
+}
+
+export interface AssetProgress {
+    name: string;
+    loaded: number;
+    failed: number;
+    total: number;
 }
 
 export interface Game extends EventTarget {
@@ -22,4 +30,7 @@ extern "C" {
 
     #[wasm_bindgen(method, getter = devMode)]
     pub fn dev_mode(this: &GameOptions) -> Option<bool>;
+
+    #[wasm_bindgen(method, getter = assetsPath)]
+    pub fn assets_path(this: &GameOptions) -> Option<String>;
 }
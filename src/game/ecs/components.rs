@@ -0,0 +1,103 @@
+use std::rc::Rc;
+
+use super::super::{
+    math::{new_scaling, new_translation, Mat4, Quat, Vec3},
+    mesh::Mesh,
+    sprite::Sprite,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub position: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl Transform {
+    pub fn from_position(position: Vec3) -> Self {
+        Self {
+            position,
+            ..Default::default()
+        }
+    }
+
+    /// Model matrix, scaling then rotating then translating.
+    pub fn matrix(&self) -> Mat4 {
+        let (p, s) = (self.position, self.scale);
+
+        new_translation(p.x(), p.y(), p.z())
+            * Mat4::from(self.rotation)
+            * new_scaling(s.x(), s.y(), s.z())
+    }
+
+    /// Transform `alpha` of the way from `self` to `other`.
+    pub fn interpolate(&self, other: &Transform, alpha: f64) -> Self {
+        Self {
+            position: self.position.lerp(&other.position, alpha),
+            rotation: self.rotation.slerp(&other.rotation, alpha),
+            scale: self.scale.lerp(&other.scale, alpha),
+        }
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            position: Vec3::default(),
+            rotation: Quat::identity(),
+            scale: Vec3::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+/// Transform at the previous fixed step, entities having it are rendered interpolated.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PreviousTransform(pub Transform);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Velocity {
+    /// Units per second.
+    pub linear: Vec3,
+    /// Rotation axis scaled by radians per second, in world space.
+    pub angular: Vec3,
+}
+
+/// Mesh drawn with the entity transform as model matrix.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeshRenderer {
+    pub mesh: Rc<Mesh>,
+    pub color: [f64; 3],
+    pub wireframe: bool,
+}
+
+impl MeshRenderer {
+    pub fn new(mesh: Rc<Mesh>, color: [f64; 3]) -> Self {
+        Self {
+            mesh,
+            color,
+            wireframe: false,
+        }
+    }
+}
+
+/// Sprite drawn over the meshes, its destination offset by the x and y of the entity transform.
+#[derive(Debug)]
+pub struct SpriteRenderer<I> {
+    pub image: Rc<I>,
+    pub sprite: Sprite,
+}
+
+impl<I> SpriteRenderer<I> {
+    pub fn new(image: Rc<I>, sprite: Sprite) -> Self {
+        Self { image, sprite }
+    }
+}
+
+impl<I> Clone for SpriteRenderer<I> {
+    fn clone(&self) -> Self {
+        Self {
+            image: self.image.clone(),
+            sprite: self.sprite,
+        }
+    }
+}
@@ -0,0 +1,396 @@
+//! Entities are generational ids, components live in one storage per type and systems are plain
+//! functions over the `World`, run in order by a `Schedule`.
+
+use std::{
+    any::{Any, TypeId},
+    cell::{Ref, RefCell},
+    collections::HashMap,
+    fmt,
+};
+
+pub use components::{MeshRenderer, PreviousTransform, SpriteRenderer, Transform, Velocity};
+pub use query::Query;
+pub use systems::{apply_velocity, render, store_previous_transforms, Schedule};
+
+use super::error::EcsError;
+
+mod components;
+mod query;
+mod systems;
+
+/// Index of an entity, with the generation telling apart the entities reusing a despawned index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+impl Entity {
+    pub fn index(&self) -> usize {
+        self.index as usize
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+/// Components of one type, indexed by entity index.
+pub struct Storage<T> {
+    components: Vec<Option<T>>,
+}
+
+impl<T> Storage<T> {
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.components.get(index).and_then(Option::as_ref)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.components.get_mut(index).and_then(Option::as_mut)
+    }
+
+    fn insert(&mut self, index: usize, component: T) -> Option<T> {
+        if index >= self.components.len() {
+            self.components.resize_with(index + 1, || None);
+        }
+
+        self.components[index].replace(component)
+    }
+
+    fn remove(&mut self, index: usize) -> Option<T> {
+        self.components.get_mut(index).and_then(Option::take)
+    }
+}
+
+impl<T> Default for Storage<T> {
+    fn default() -> Self {
+        Self {
+            components: Vec::new(),
+        }
+    }
+}
+
+trait AnyStorage {
+    fn remove(&mut self, index: usize);
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: 'static> AnyStorage for RefCell<Storage<T>> {
+    fn remove(&mut self, index: usize) {
+        self.get_mut().remove(index);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Components added together by `World::spawn`, implemented for tuples of up to 6 components.
+pub trait Bundle {
+    fn insert_into(self, world: &mut World, entity: Entity);
+}
+
+macro_rules! impl_bundle {
+    ($($name:ident),*) => {
+        impl<$($name: 'static),*> Bundle for ($($name,)*) {
+            #[allow(non_snake_case, unused_variables)]
+            fn insert_into(self, world: &mut World, entity: Entity) {
+                let ($($name,)*) = self;
+
+                $(world.storage_mut::<$name>().insert(entity.index(), $name);)*
+            }
+        }
+    };
+}
+
+impl_bundle!();
+impl_bundle!(A);
+impl_bundle!(A, B);
+impl_bundle!(A, B, C);
+impl_bundle!(A, B, C, D);
+impl_bundle!(A, B, C, D, E);
+impl_bundle!(A, B, C, D, E, F);
+
+#[derive(Default)]
+pub struct World {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free: Vec<u32>,
+    storages: HashMap<TypeId, Box<dyn AnyStorage>>,
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn spawn(&mut self, components: impl Bundle) -> Entity {
+        let entity = match self.free.pop() {
+            Some(index) => {
+                self.alive[index as usize] = true;
+
+                Entity {
+                    index,
+                    generation: self.generations[index as usize],
+                }
+            }
+            None => {
+                self.generations.push(0);
+                self.alive.push(true);
+
+                Entity {
+                    index: self.generations.len() as u32 - 1,
+                    generation: 0,
+                }
+            }
+        };
+
+        components.insert_into(self, entity);
+
+        entity
+    }
+
+    /// Removes the entity and its components, its index is reused with the next generation.
+    pub fn despawn(&mut self, entity: Entity) -> Result<(), EcsError> {
+        self.check(entity)?;
+
+        for storage in self.storages.values_mut() {
+            storage.remove(entity.index());
+        }
+
+        self.alive[entity.index()] = false;
+        self.generations[entity.index()] += 1;
+        self.free.push(entity.index);
+
+        Ok(())
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.check(entity).is_ok()
+    }
+
+    /// Number of live entities.
+    pub fn len(&self) -> usize {
+        self.alive.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.generations
+            .iter()
+            .zip(&self.alive)
+            .enumerate()
+            .filter(|(_, (_, alive))| **alive)
+            .map(|(index, (generation, _))| Entity {
+                index: index as u32,
+                generation: *generation,
+            })
+    }
+
+    /// Despawns every entity, ids handed out before stay invalid.
+    pub fn clear(&mut self) {
+        for entity in self.entities().collect::<Vec<_>>() {
+            self.despawn(entity).ok();
+        }
+    }
+
+    /// Returns the component `entity` had of this type.
+    pub fn insert<T: 'static>(
+        &mut self,
+        entity: Entity,
+        component: T,
+    ) -> Result<Option<T>, EcsError> {
+        self.check(entity)?;
+
+        Ok(self.storage_mut::<T>().insert(entity.index(), component))
+    }
+
+    pub fn remove<T: 'static>(&mut self, entity: Entity) -> Result<Option<T>, EcsError> {
+        self.check(entity)?;
+
+        Ok(self.storage_mut::<T>().remove(entity.index()))
+    }
+
+    /// Panics if the components of this type are borrowed mutably, as during a query.
+    pub fn get<T: 'static>(&self, entity: Entity) -> Option<Ref<'_, T>> {
+        self.check(entity).ok()?;
+
+        Ref::filter_map(self.storage::<T>()?.borrow(), |storage| {
+            storage.get(entity.index())
+        })
+        .ok()
+    }
+
+    pub fn get_mut<T: 'static>(&mut self, entity: Entity) -> Option<&mut T> {
+        self.check(entity).ok()?;
+
+        self.storages
+            .get_mut(&TypeId::of::<T>())?
+            .as_any_mut()
+            .downcast_mut::<RefCell<Storage<T>>>()?
+            .get_mut()
+            .get_mut(entity.index())
+    }
+
+    pub fn has<T: 'static>(&self, entity: Entity) -> bool {
+        self.get::<T>(entity).is_some()
+    }
+
+    /// Calls `f` with every live entity having all the components of `Q`, a tuple of `&T`,
+    /// `&mut T` and `Option<&T>`. Panics if `Q` names a component type twice, once as `&mut T`.
+    ///
+    /// ```
+    /// # use rust_game::game::ecs::{Transform, Velocity, World};
+    /// # let (world, dt) = (World::new(), 0.1);
+    /// world.query::<(&mut Transform, &Velocity)>(|_, (transform, velocity)| {
+    ///     transform.position = transform.position + velocity.linear * dt;
+    /// });
+    /// ```
+    pub fn query<Q: Query>(&self, mut f: impl FnMut(Entity, Q::Item<'_>)) {
+        let Some(mut borrow) = Q::borrow(self) else {
+            return;
+        };
+
+        for entity in self.entities() {
+            if let Some(item) = Q::fetch(&mut borrow, entity.index()) {
+                f(entity, item);
+            }
+        }
+    }
+
+    fn storage<T: 'static>(&self) -> Option<&RefCell<Storage<T>>> {
+        self.storages
+            .get(&TypeId::of::<T>())?
+            .as_any()
+            .downcast_ref::<RefCell<Storage<T>>>()
+    }
+
+    fn storage_mut<T: 'static>(&mut self) -> &mut Storage<T> {
+        self.storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(RefCell::new(Storage::<T>::default())))
+            .as_any_mut()
+            .downcast_mut::<RefCell<Storage<T>>>()
+            .expect("storage registered under the TypeId of its component")
+            .get_mut()
+    }
+
+    fn check(&self, entity: Entity) -> Result<(), EcsError> {
+        let index = entity.index();
+
+        if self.alive.get(index) == Some(&true) && self.generations[index] == entity.generation {
+            Ok(())
+        } else {
+            Err(EcsError::NoSuchEntity(entity))
+        }
+    }
+}
+
+impl fmt::Debug for World {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("World")
+            .field("entities", &self.len())
+            .field("component_types", &self.storages.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Health(u32);
+
+    #[derive(Debug, PartialEq)]
+    struct Name(&'static str);
+
+    #[test]
+    fn test_generational_ids() {
+        let mut world = World::new();
+        let a = world.spawn((Health(10),));
+        let b = world.spawn(());
+
+        world.despawn(a).unwrap();
+
+        let c = world.spawn(());
+
+        assert_eq!(c.index(), a.index());
+        assert_ne!(c, a);
+        assert!(!world.is_alive(a));
+        assert!(world.is_alive(b) && world.is_alive(c));
+        assert_eq!(world.len(), 2);
+        assert!(world.get::<Health>(c).is_none());
+        assert_eq!(world.despawn(a), Err(EcsError::NoSuchEntity(a)));
+        assert_eq!(
+            world.insert(a, Health(1)).unwrap_err(),
+            EcsError::NoSuchEntity(a)
+        );
+    }
+
+    #[test]
+    fn test_components() {
+        let mut world = World::new();
+        let entity = world.spawn((Health(10), Name("hero")));
+
+        assert_eq!(world.insert(entity, Health(5)).unwrap(), Some(Health(10)));
+
+        world.get_mut::<Health>(entity).unwrap().0 -= 1;
+
+        assert_eq!(*world.get::<Health>(entity).unwrap(), Health(4));
+        assert_eq!(world.remove::<Name>(entity).unwrap(), Some(Name("hero")));
+        assert!(!world.has::<Name>(entity));
+        assert!(world.get::<u8>(entity).is_none());
+    }
+
+    #[test]
+    fn test_query() {
+        let mut world = World::new();
+        let hero = world.spawn((Health(10), Name("hero")));
+        let rock = world.spawn((Name("rock"),));
+        let ghost = world.spawn((Health(3),));
+
+        world.despawn(ghost).unwrap();
+
+        world.query::<(&mut Health, &Name)>(|_, (health, _)| health.0 *= 2);
+
+        let mut found = Vec::new();
+
+        world.query::<(&Name, Option<&Health>)>(|entity, (name, health)| {
+            found.push((entity, name.0, health.map(|h| h.0)));
+        });
+
+        assert_eq!(found, vec![(hero, "hero", Some(20)), (rock, "rock", None)]);
+
+        let mut count = 0;
+
+        world.query::<(&Health, &u8)>(|_, _| count += 1);
+
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut world = World::new();
+        let entity = world.spawn((Health(1),));
+
+        world.clear();
+
+        assert!(world.is_empty());
+        assert!(!world.is_alive(entity));
+
+        let entity = world.spawn(());
+
+        assert!(world.get::<Health>(entity).is_none());
+    }
+}
@@ -0,0 +1,83 @@
+use std::cell::{Ref, RefMut};
+
+use super::{Storage, World};
+
+/// Components fetched together by `World::query`: `&T`, `&mut T`, `Option<&T>` and tuples of up
+/// to 6 of them.
+pub trait Query {
+    /// Storages held for the duration of the query.
+    type Borrow<'w>;
+    type Item<'b>;
+
+    /// `None` when a required component type was never inserted.
+    fn borrow(world: &World) -> Option<Self::Borrow<'_>>;
+
+    /// `None` when the entity at `index` lacks a required component.
+    fn fetch<'b>(borrow: &'b mut Self::Borrow<'_>, index: usize) -> Option<Self::Item<'b>>;
+}
+
+impl<T: 'static> Query for &T {
+    type Borrow<'w> = Ref<'w, Storage<T>>;
+    type Item<'b> = &'b T;
+
+    fn borrow(world: &World) -> Option<Self::Borrow<'_>> {
+        Some(world.storage::<T>()?.borrow())
+    }
+
+    fn fetch<'b>(borrow: &'b mut Self::Borrow<'_>, index: usize) -> Option<Self::Item<'b>> {
+        borrow.get(index)
+    }
+}
+
+impl<T: 'static> Query for &mut T {
+    type Borrow<'w> = RefMut<'w, Storage<T>>;
+    type Item<'b> = &'b mut T;
+
+    fn borrow(world: &World) -> Option<Self::Borrow<'_>> {
+        Some(world.storage::<T>()?.borrow_mut())
+    }
+
+    fn fetch<'b>(borrow: &'b mut Self::Borrow<'_>, index: usize) -> Option<Self::Item<'b>> {
+        borrow.get_mut(index)
+    }
+}
+
+impl<T: 'static> Query for Option<&T> {
+    type Borrow<'w> = Option<Ref<'w, Storage<T>>>;
+    type Item<'b> = Option<&'b T>;
+
+    fn borrow(world: &World) -> Option<Self::Borrow<'_>> {
+        Some(world.storage::<T>().map(|storage| storage.borrow()))
+    }
+
+    fn fetch<'b>(borrow: &'b mut Self::Borrow<'_>, index: usize) -> Option<Self::Item<'b>> {
+        Some(borrow.as_ref().and_then(|storage| storage.get(index)))
+    }
+}
+
+macro_rules! impl_query {
+    ($($name:ident),+) => {
+        impl<$($name: Query),+> Query for ($($name,)+) {
+            type Borrow<'w> = ($($name::Borrow<'w>,)+);
+            type Item<'b> = ($($name::Item<'b>,)+);
+
+            fn borrow(world: &World) -> Option<Self::Borrow<'_>> {
+                Some(($($name::borrow(world)?,)+))
+            }
+
+            #[allow(non_snake_case)]
+            fn fetch<'b>(borrow: &'b mut Self::Borrow<'_>, index: usize) -> Option<Self::Item<'b>> {
+                let ($($name,)+) = borrow;
+
+                Some(($($name::fetch($name, index)?,)+))
+            }
+        }
+    };
+}
+
+impl_query!(A);
+impl_query!(A, B);
+impl_query!(A, B, C);
+impl_query!(A, B, C, D);
+impl_query!(A, B, C, D, E);
+impl_query!(A, B, C, D, E, F);
@@ -0,0 +1,219 @@
+use std::fmt;
+
+use super::{
+    super::{backend::RenderBackend, math::Quat, renderer::Renderer, sprite::Sprite},
+    MeshRenderer, PreviousTransform, SpriteRenderer, Transform, Velocity, World,
+};
+
+type System = Box<dyn FnMut(&mut World, f64)>;
+
+/// Named systems run in the order they were added, once per fixed step.
+#[derive(Default)]
+pub struct Schedule {
+    systems: Vec<(String, System)>,
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `system` is called with the world and the step duration in seconds.
+    pub fn add_system(
+        &mut self,
+        name: &str,
+        system: impl FnMut(&mut World, f64) + 'static,
+    ) -> &mut Self {
+        self.systems.push((name.to_string(), Box::new(system)));
+        self
+    }
+
+    pub fn remove_system(&mut self, name: &str) -> bool {
+        let len = self.systems.len();
+
+        self.systems.retain(|(n, _)| n != name);
+        self.systems.len() != len
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.systems.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn run(&mut self, world: &mut World, dt: f64) {
+        for (_, system) in &mut self.systems {
+            system(world, dt);
+        }
+    }
+}
+
+impl fmt::Debug for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+/// Keeps the transform of the last step for interpolation, run it before anything moves.
+pub fn store_previous_transforms(world: &mut World, _dt: f64) {
+    world.query::<(&Transform, &mut PreviousTransform)>(|_, (transform, previous)| {
+        previous.0 = *transform;
+    });
+}
+
+pub fn apply_velocity(world: &mut World, dt: f64) {
+    world.query::<(&mut Transform, &Velocity)>(|_, (transform, velocity)| {
+        transform.position = transform.position + velocity.linear * dt;
+
+        let speed = velocity.angular.length();
+
+        if speed > 0.0 {
+            let spin = Quat::from_axis_angle(velocity.angular / speed, speed * dt);
+
+            transform.rotation = (spin * transform.rotation).normalize();
+        }
+    });
+}
+
+/// Draws the meshes, then the sprites on top, `alpha` of the way between the previous and last
+/// transforms.
+pub fn render<B: RenderBackend>(world: &World, renderer: &mut Renderer<B>, alpha: f64)
+where
+    B::Image: 'static,
+{
+    let interpolate = |transform: &Transform, previous: Option<&PreviousTransform>| {
+        previous.map_or(*transform, |previous| {
+            previous.0.interpolate(transform, alpha)
+        })
+    };
+
+    world.query::<(&Transform, Option<&PreviousTransform>, &MeshRenderer)>(
+        |_, (transform, previous, mesh_renderer)| {
+            let model = interpolate(transform, previous).matrix();
+
+            if mesh_renderer.wireframe {
+                renderer.draw_mesh(&mesh_renderer.mesh, &model);
+            } else {
+                renderer.fill_mesh(&mesh_renderer.mesh, &model, mesh_renderer.color);
+            }
+        },
+    );
+
+    world.query::<(
+        &Transform,
+        Option<&PreviousTransform>,
+        &SpriteRenderer<B::Image>,
+    )>(|_, (transform, previous, sprite_renderer)| {
+        let position = interpolate(transform, previous).position;
+        let mut destination = sprite_renderer.sprite.destination;
+
+        destination.x += position.x();
+        destination.y += position.y();
+
+        renderer.draw_sprite(
+            &sprite_renderer.image,
+            &Sprite {
+                destination,
+                ..sprite_renderer.sprite
+            },
+        );
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{f64::consts::FRAC_PI_2, rc::Rc};
+
+    use super::super::super::{
+        backend::{DrawCommand, MemoryBackend, MemoryImage, Rect},
+        math::Vec3,
+        mesh::Mesh,
+    };
+    use super::*;
+
+    #[test]
+    fn test_schedule_order() {
+        let mut world = World::new();
+        let entity = world.spawn((Vec::<&str>::new(),));
+        let mut schedule = Schedule::new();
+
+        let push = |name: &'static str| {
+            move |world: &mut World, _dt: f64| {
+                world.query::<&mut Vec<&str>>(|_, log| log.push(name));
+            }
+        };
+
+        schedule
+            .add_system("input", push("input"))
+            .add_system("physics", push("physics"))
+            .add_system("ai", push("ai"));
+
+        assert!(schedule.remove_system("ai"));
+        assert!(!schedule.remove_system("ai"));
+
+        schedule.run(&mut world, 0.1);
+
+        assert_eq!(schedule.names().collect::<Vec<_>>(), ["input", "physics"]);
+        assert_eq!(
+            *world.get::<Vec<&str>>(entity).unwrap(),
+            ["input", "physics"]
+        );
+    }
+
+    #[test]
+    fn test_apply_velocity() {
+        let mut world = World::new();
+        let entity = world.spawn((
+            Transform::default(),
+            PreviousTransform::default(),
+            Velocity {
+                linear: Vec3::new(2.0, 0.0, 0.0),
+                angular: Vec3::new(0.0, FRAC_PI_2, 0.0),
+            },
+        ));
+
+        store_previous_transforms(&mut world, 0.5);
+        apply_velocity(&mut world, 0.5);
+
+        let transform = *world.get::<Transform>(entity).unwrap();
+        let turned = transform.rotation.rotate(Vec3::new(1.0, 0.0, 0.0));
+
+        assert!((transform.position - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-9);
+        assert!((turned.x() - std::f64::consts::FRAC_1_SQRT_2).abs() < 1e-9);
+        assert_eq!(
+            world.get::<PreviousTransform>(entity).unwrap().0,
+            Transform::default()
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let mut world = World::new();
+        let mut renderer = Renderer::new(MemoryBackend::new(48, 36));
+        let image = Rc::new(MemoryImage {
+            name: "coin".to_string(),
+            width: 16.0,
+            height: 16.0,
+        });
+
+        world.spawn((
+            Transform::default(),
+            MeshRenderer::new(Rc::new(Mesh::cube(1.0)), [1.0, 1.0, 1.0]),
+        ));
+        world.spawn((
+            Transform::from_position(Vec3::new(10.0, 20.0, 0.0)),
+            PreviousTransform(Transform::from_position(Vec3::new(0.0, 20.0, 0.0))),
+            SpriteRenderer::new(
+                image,
+                Sprite::new(Rect::new(0.0, 0.0, 16.0, 16.0), 1.0, 2.0),
+            ),
+        ));
+
+        render(&world, &mut renderer, 0.5);
+
+        assert!(renderer.backend().triangles().count() > 0);
+        assert!(renderer.backend().commands.iter().any(|command| matches!(
+            command,
+            DrawCommand::Image { name, destination, .. }
+                if name == "coin" && *destination == Rect::new(6.0, 22.0, 16.0, 16.0)
+        )));
+    }
+}
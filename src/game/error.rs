@@ -1,6 +1,10 @@
+use js_sys::Reflect;
 use thiserror::Error;
+use wasm_bindgen::JsValue;
 
-#[derive(Debug, Error)]
+use super::ecs::Entity;
+
+#[derive(Debug, Clone, Error)]
 #[non_exhaustive]
 pub enum InitError {
     #[error("no window")]
@@ -13,4 +17,173 @@ pub enum InitError {
     NoContainer,
     #[error("failed to append child\n{0}")]
     AppendChildFailed(String),
+    #[error("failed to acquire rendering context\n{0}")]
+    ContextUnavailable(String),
+    #[error("failed to load asset {0}")]
+    AssetLoadFailed(String),
+}
+
+impl InitError {
+    /// Stable identifier exposed to JavaScript as the `code` of the thrown `Error`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            InitError::NoWindow => "NO_WINDOW",
+            InitError::NoDocument => "NO_DOCUMENT",
+            InitError::ElementCreationFailed(_) => "ELEMENT_CREATION_FAILED",
+            InitError::NoContainer => "NO_CONTAINER",
+            InitError::AppendChildFailed(_) => "APPEND_CHILD_FAILED",
+            InitError::ContextUnavailable(_) => "CONTEXT_UNAVAILABLE",
+            InitError::AssetLoadFailed(_) => "ASSET_LOAD_FAILED",
+        }
+    }
+}
+
+impl From<InitError> for JsValue {
+    fn from(err: InitError) -> Self {
+        js_error(&err.to_string(), err.code())
+    }
+}
+
+#[derive(Debug, Clone, Error, PartialEq)]
+#[non_exhaustive]
+pub enum ObjError {
+    #[error("line {0}: expected {1} values")]
+    MissingValues(usize, usize),
+    #[error("line {0}: invalid number `{1}`")]
+    InvalidNumber(usize, String),
+    #[error("line {0}: index {1} out of range")]
+    IndexOutOfRange(usize, i64),
+    #[error("line {0}: a face needs at least 3 vertices")]
+    DegenerateFace(usize),
+    #[error("line {0}: no material declared with newmtl")]
+    NoMaterial(usize),
+}
+
+#[derive(Debug, Clone, Error)]
+#[non_exhaustive]
+pub enum AssetError {
+    #[error(transparent)]
+    Init(#[from] InitError),
+    /// The request did not complete, e.g. the network dropped.
+    #[error("failed to fetch {0}\n{1}")]
+    FetchFailed(String, String),
+    /// The server answered with an error status other than not found.
+    #[error("failed to fetch {0}\nstatus {1}")]
+    HttpStatus(String, u16),
+    #[error("failed to parse {0}\n{1}")]
+    ParseFailed(String, ObjError),
+    #[error("asset not found {0}")]
+    NotFound(String),
+    #[error("failed to decode {0}")]
+    DecodeFailed(String),
+    #[error("timed out loading {0}")]
+    Timeout(String),
+    #[error("invalid asset manifest {0}\n{1}")]
+    InvalidManifest(String, String),
+}
+
+impl AssetError {
+    /// Whether trying again may succeed, e.g. a dropped request or an overloaded server rather
+    /// than a missing file or a denied access.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            AssetError::FetchFailed(..) | AssetError::Timeout(_) => true,
+            AssetError::HttpStatus(_, status) => matches!(status, 408 | 429 | 500..=599),
+            _ => false,
+        }
+    }
+
+    /// Stable identifier exposed to JavaScript as the `code` of the thrown `Error`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AssetError::Init(err) => err.code(),
+            AssetError::FetchFailed(..) => "FETCH_FAILED",
+            AssetError::HttpStatus(..) => "HTTP_STATUS",
+            AssetError::ParseFailed(..) => "PARSE_FAILED",
+            AssetError::NotFound(_) => "NOT_FOUND",
+            AssetError::DecodeFailed(_) => "DECODE_FAILED",
+            AssetError::Timeout(_) => "TIMEOUT",
+            AssetError::InvalidManifest(..) => "INVALID_MANIFEST",
+        }
+    }
+}
+
+impl From<AssetError> for JsValue {
+    fn from(err: AssetError) -> Self {
+        js_error(&err.to_string(), err.code())
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+#[non_exhaustive]
+pub enum AnimationError {
+    #[error("unknown animation state `{0}`")]
+    UnknownState(String),
+    #[error("animation `{0}` has no frames")]
+    NoFrames(String),
+}
+
+#[derive(Debug, Error, PartialEq)]
+#[non_exhaustive]
+pub enum EcsError {
+    #[error("entity {0:?} does not exist")]
+    NoSuchEntity(Entity),
+}
+
+fn js_error(message: &str, code: &str) -> JsValue {
+    let error = js_sys::Error::new(message);
+
+    Reflect::set(&error, &"code".into(), &code.into()).ok();
+
+    error.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn test_init_error_codes_are_unique() {
+        let errors = [
+            InitError::NoWindow,
+            InitError::NoDocument,
+            InitError::ElementCreationFailed(String::new()),
+            InitError::NoContainer,
+            InitError::AppendChildFailed(String::new()),
+            InitError::ContextUnavailable(String::new()),
+            InitError::AssetLoadFailed(String::new()),
+        ];
+        let codes: HashSet<_> = errors.iter().map(InitError::code).collect();
+
+        assert_eq!(codes.len(), errors.len());
+        assert_eq!(
+            InitError::ContextUnavailable(String::new()).code(),
+            "CONTEXT_UNAVAILABLE"
+        );
+    }
+
+    #[test]
+    fn test_asset_error_is_retryable() {
+        let url = || "/assets/player.png".to_string();
+
+        assert!(AssetError::Timeout(url()).is_retryable());
+        assert!(AssetError::FetchFailed(url(), "network error".to_string()).is_retryable());
+
+        for status in [408, 429, 500, 503] {
+            assert!(AssetError::HttpStatus(url(), status).is_retryable());
+        }
+
+        for status in [400, 401, 403] {
+            assert!(!AssetError::HttpStatus(url(), status).is_retryable());
+        }
+
+        assert!(!AssetError::NotFound(url()).is_retryable());
+        assert!(!AssetError::DecodeFailed(url()).is_retryable());
+        assert_eq!(
+            AssetError::NotFound(url()).to_string(),
+            "asset not found /assets/player.png"
+        );
+    }
 }
@@ -1,83 +1,129 @@
 use std::{cell::RefCell, rc::Rc};
 
 use super::{
-    error::InitError, handler::EventHandler, logic::GameLogic, renderer::Renderer, GameOptions,
+    assets::{AssetProgress, Assets},
+    assets_loader::AssetsLoader,
+    backend::CanvasBackend,
+    camera::ProjectionMode,
+    error::InitError,
+    gamepad::GamepadPoller,
+    handler::EventHandler,
+    input::{InputEvent, InputMap},
+    logic::GameLogic,
+    math::Vec3,
+    renderer::Renderer,
+    scene::{Scene, SceneManager},
+    touch::VirtualJoystick,
+    GameOptions,
 };
+use js_sys::{Object, Promise, Reflect};
 use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::future_to_promise;
 use web_sys::{
-    window, CanvasRenderingContext2d, Event, EventTarget, HtmlCanvasElement, HtmlElement,
+    window, CanvasRenderingContext2d, CustomEvent, CustomEventInit, Event, EventTarget,
+    GamepadEvent, GamepadMappingType, HtmlCanvasElement, HtmlElement, HtmlImageElement,
+    PointerEvent,
 };
 
+type AnimationFrameCallback = Closure<dyn FnMut(f64)>;
+
 #[wasm_bindgen]
 pub struct Game {
     canvas: Rc<HtmlCanvasElement>,
     event_target: Rc<RefCell<Option<EventTarget>>>,
     request_id: Rc<RefCell<Option<i32>>>,
     game_logic: Rc<RefCell<GameLogic>>,
+    scenes: Rc<RefCell<SceneManager>>,
     renderer: Rc<RefCell<Renderer>>,
-    keydown_handler: Option<EventHandler>,
-    keyup_handler: Option<EventHandler>,
-    animation_frame_callback: Rc<RefCell<Option<Closure<dyn FnMut()>>>>,
+    assets: Rc<RefCell<Assets>>,
+    assets_loader: Rc<AssetsLoader>,
+    input_handlers: Vec<EventHandler>,
+    gamepads: Rc<RefCell<GamepadPoller>>,
+    animation_frame_callback: Rc<RefCell<Option<AnimationFrameCallback>>>,
 }
 
 #[wasm_bindgen]
 impl Game {
+    /// Throws an `Error` whose `code` identifies the `InitError`.
     #[wasm_bindgen(constructor)]
-    pub fn new(options: GameOptions) -> Self {
-        let canvas = create_canvas(options.container()).unwrap_throw();
-        let rendering_context = canvas
-            .get_context("2d")
-            .unwrap_throw()
-            .unwrap_throw()
-            .dyn_into::<CanvasRenderingContext2d>()
-            .unwrap_throw();
+    pub fn new(options: GameOptions) -> Result<Game, JsValue> {
+        let canvas = create_canvas(options.container())?;
+        let rendering_context = match create_context(&canvas) {
+            Ok(context) => context,
+            Err(err) => {
+                canvas.remove();
+
+                return Err(err.into());
+            }
+        };
 
-        let renderer = Rc::new(RefCell::new(Renderer::new(
+        let renderer = Rc::new(RefCell::new(Renderer::new(CanvasBackend::new(
             rendering_context,
             canvas.width(),
             canvas.height(),
-        )));
+        ))));
 
         let canvas = Rc::new(canvas);
         let game_logic = Rc::new(RefCell::new(GameLogic::default()));
+        let assets_loader = options
+            .assets_path()
+            .map_or_else(AssetsLoader::default, |path| AssetsLoader::new(&path));
 
-        Self {
+        Ok(Self {
             canvas,
             event_target: Rc::new(RefCell::new(None)),
             request_id: Rc::new(RefCell::new(None)),
             game_logic,
+            scenes: Rc::new(RefCell::new(SceneManager::new())),
             renderer,
-            keydown_handler: None,
-            keyup_handler: None,
+            assets: Rc::new(RefCell::new(Assets::default())),
+            assets_loader: Rc::new(assets_loader),
+            input_handlers: Vec::new(),
+            gamepads: Rc::new(RefCell::new(GamepadPoller::new())),
             animation_frame_callback: Rc::new(RefCell::new(None)),
-        }
+        })
     }
 
+    /// Starts the frame loop, with the cube demo as the scene when none was pushed.
     pub fn run(&mut self) {
         if self.request_id.borrow().is_some() {
             return;
         }
 
-        self.game_logic.borrow_mut().setup();
+        if self.scenes.borrow().is_empty() {
+            self.push_scene(self.game_logic.clone());
+        }
+
         self.attach_event_handlers();
+        self.gamepads.borrow_mut().connect_present();
 
         self.animation_frame_callback.replace(Some({
             let cb = self.animation_frame_callback.clone();
-            let game_logic = self.game_logic.clone();
+            let scenes = self.scenes.clone();
             let renderer = self.renderer.clone();
+            let gamepads = self.gamepads.clone();
             let request_id = self.request_id.clone();
             let event_target = self.event_target.clone();
 
-            Closure::new(move || {
+            Closure::new(move |timestamp: f64| {
                 if let Some(event_target) = event_target.borrow().as_ref() {
                     event_target
                         .dispatch_event(&Event::new("frame").unwrap_throw())
                         .unwrap_throw();
                 }
 
-                game_logic.borrow_mut().draw(&mut renderer.borrow_mut());
+                // gamepads send no events for their buttons and sticks, they are read each frame
+                for event in gamepads.borrow_mut().poll() {
+                    scenes
+                        .borrow_mut()
+                        .handle_input(&event, &mut renderer.borrow_mut());
+                }
 
-                if game_logic.borrow().is_running() {
+                scenes
+                    .borrow_mut()
+                    .frame(&mut renderer.borrow_mut(), timestamp);
+
+                if !scenes.borrow().is_empty() {
                     request_id
                         .borrow_mut()
                         .replace(request_animation_frame(cb.borrow().as_ref().unwrap()));
@@ -100,15 +146,24 @@ impl Game {
         }
     }
 
+    /// Restarts the scenes on the stack from their setup.
     pub fn reset(&mut self) {
         self.stop();
+        self.scenes
+            .borrow_mut()
+            .restart(&mut self.renderer.borrow_mut());
         self.run();
     }
 
+    /// Stops the frame loop, the scenes stay on the stack and carry on at the next `run`.
     pub fn stop(&mut self) {
         self.detach_event_handlers();
         self.animation_frame_callback.take();
 
+        // the input released while stopped is never reported
+        release_input(&self.scenes, &self.renderer, &self.gamepads);
+        self.scenes.borrow_mut().timestep_mut().reset();
+
         if let Some(request_id) = self.request_id.borrow_mut().take() {
             cancel_animation_frame(request_id);
 
@@ -120,32 +175,341 @@ impl Game {
         }
     }
 
-    fn attach_event_handlers(&mut self) {
-        let keydown_handler = {
-            let game_logic = self.game_logic.clone();
-            let window = Rc::new(window().unwrap_throw());
+    #[wasm_bindgen(getter)]
+    pub fn orthographic(&self) -> bool {
+        self.renderer.borrow().projection_mode() == ProjectionMode::Orthographic
+    }
 
-            EventHandler::new(window, "keydown", move |event| {
-                game_logic.borrow_mut().on_keydown(event.unchecked_ref());
-            })
+    #[wasm_bindgen(setter)]
+    pub fn set_orthographic(&self, orthographic: bool) {
+        self.renderer
+            .borrow_mut()
+            .set_projection_mode(if orthographic {
+                ProjectionMode::Orthographic
+            } else {
+                ProjectionMode::Perspective
+            });
+        self.dispatch_projection_change();
+    }
+
+    /// Vertical field of view in radians.
+    #[wasm_bindgen(getter)]
+    pub fn fov(&self) -> f64 {
+        self.renderer.borrow().fov()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_fov(&self, fov: f64) -> Result<(), JsError> {
+        if !(fov > 0.0 && fov < std::f64::consts::PI) {
+            return Err(JsError::new("fov must be between 0 and PI radians"));
+        }
+
+        self.renderer.borrow_mut().set_fov(fov);
+        self.dispatch_projection_change();
+
+        Ok(())
+    }
+
+    #[wasm_bindgen(getter = zNear)]
+    pub fn z_near(&self) -> f64 {
+        self.renderer.borrow().z_near()
+    }
+
+    #[wasm_bindgen(setter = zNear)]
+    pub fn set_z_near(&self, z_near: f64) -> Result<(), JsError> {
+        if !(z_near > 0.0 && z_near < self.z_far()) {
+            return Err(JsError::new("zNear must be positive and less than zFar"));
+        }
+
+        self.renderer.borrow_mut().set_z_near(z_near);
+        self.dispatch_projection_change();
+
+        Ok(())
+    }
+
+    #[wasm_bindgen(getter = zFar)]
+    pub fn z_far(&self) -> f64 {
+        self.renderer.borrow().z_far()
+    }
+
+    #[wasm_bindgen(setter = zFar)]
+    pub fn set_z_far(&self, z_far: f64) -> Result<(), JsError> {
+        if !(z_far > self.z_near() && z_far.is_finite()) {
+            return Err(JsError::new("zFar must be finite and greater than zNear"));
+        }
+
+        self.renderer.borrow_mut().set_z_far(z_far);
+        self.dispatch_projection_change();
+
+        Ok(())
+    }
+
+    /// Camera position as `[x, y, z]`.
+    #[wasm_bindgen(getter = cameraPosition)]
+    pub fn camera_position(&self) -> Vec<f64> {
+        self.renderer.borrow().camera().position.as_ref().to_vec()
+    }
+
+    #[wasm_bindgen(setter = cameraPosition)]
+    pub fn set_camera_position(&self, position: Vec<f64>) -> Result<(), JsError> {
+        let [x, y, z] = position[..] else {
+            return Err(JsError::new("cameraPosition must have 3 components"));
         };
 
-        let keyup_handler = {
-            let game_logic = self.game_logic.clone();
-            let window = Rc::new(window().unwrap_throw());
+        self.game_logic
+            .borrow_mut()
+            .set_camera_position(&mut self.renderer.borrow_mut(), Vec3::new(x, y, z));
+        self.dispatch_projection_change();
+
+        Ok(())
+    }
+
+    /// Simulation steps per second, independent of the display refresh rate.
+    #[wasm_bindgen(getter = tickRate)]
+    pub fn tick_rate(&self) -> f64 {
+        self.scenes.borrow().timestep().tick_rate()
+    }
+
+    #[wasm_bindgen(setter = tickRate)]
+    pub fn set_tick_rate(&self, tick_rate: f64) -> Result<(), JsError> {
+        if !(tick_rate > 0.0 && tick_rate.is_finite()) {
+            return Err(JsError::new("tickRate must be a positive number"));
+        }
+
+        self.scenes
+            .borrow_mut()
+            .timestep_mut()
+            .set_tick_rate(tick_rate);
+
+        Ok(())
+    }
+
+    /// Bindings of the demo actions and axes as JSON, see `InputMap`.
+    #[wasm_bindgen(getter = inputMap)]
+    pub fn input_map(&self) -> String {
+        self.game_logic.borrow().input_map().to_json()
+    }
+
+    #[wasm_bindgen(setter = inputMap)]
+    pub fn set_input_map(&self, json: &str) -> Result<(), JsError> {
+        let input_map: InputMap = json.parse()?;
+
+        self.game_logic
+            .borrow_mut()
+            .input_map_mut()
+            .set_bindings(input_map);
+
+        Ok(())
+    }
+
+    /// Hides the cursor and reports raw mouse movements, the browser only grants it from a user
+    /// gesture such as a click.
+    #[wasm_bindgen(js_name = requestPointerLock)]
+    pub fn request_pointer_lock(&self) {
+        self.canvas.request_pointer_lock();
+    }
+
+    #[wasm_bindgen(js_name = exitPointerLock)]
+    pub fn exit_pointer_lock(&self) {
+        if let Some(document) = window().and_then(|window| window.document()) {
+            document.exit_pointer_lock();
+        }
+    }
+
+    #[wasm_bindgen(getter = pointerLocked)]
+    pub fn pointer_locked(&self) -> bool {
+        is_pointer_locked(&self.canvas)
+    }
+
+    /// Whether the on-screen joystick, turning the cube like the arrow keys, is shown in the
+    /// bottom left corner.
+    #[wasm_bindgen(getter = virtualJoystick)]
+    pub fn virtual_joystick(&self) -> bool {
+        self.game_logic.borrow().virtual_joystick().is_some()
+    }
+
+    #[wasm_bindgen(setter = virtualJoystick)]
+    pub fn set_virtual_joystick(&self, shown: bool) {
+        let joystick = shown.then(|| {
+            VirtualJoystick::bottom_left(self.canvas.width() as f64, self.canvas.height() as f64)
+        });
 
-            EventHandler::new(window, "keyup", move |event| {
-                game_logic.borrow_mut().on_keyup(event.unchecked_ref());
+        self.game_logic.borrow_mut().set_virtual_joystick(joystick);
+    }
+
+    /// Stick tilt, between 0 and 1, below which gamepad sticks read as centered.
+    #[wasm_bindgen(getter = gamepadDeadzone)]
+    pub fn gamepad_deadzone(&self) -> f64 {
+        self.gamepads.borrow().deadzone()
+    }
+
+    #[wasm_bindgen(setter = gamepadDeadzone)]
+    pub fn set_gamepad_deadzone(&self, deadzone: f64) {
+        self.gamepads.borrow_mut().set_deadzone(deadzone);
+    }
+
+    /// Loads the manifest at `manifest`, relative to the assets path, and every asset it lists.
+    /// Dispatches `assetprogress` as each asset settles and `assetsloaded` once they all did, the
+    /// promise rejects with the first failure.
+    #[wasm_bindgen(js_name = loadAssets)]
+    pub fn load_assets(&self, manifest: String) -> Promise {
+        let assets = self.assets.clone();
+        let assets_loader = self.assets_loader.clone();
+        let event_target = self.event_target.clone();
+
+        future_to_promise(async move {
+            let manifest = assets_loader.load_manifest(&manifest).await?;
+            let mut last = AssetProgress::default();
+
+            let result = Assets::preload(&assets, &assets_loader, &manifest, |progress| {
+                dispatch_custom_event(&event_target, "assetprogress", &progress_detail(progress));
+                last = progress.clone();
             })
-        };
+            .await;
+
+            dispatch_custom_event(&event_target, "assetsloaded", &progress_detail(&last));
+
+            result?;
+
+            Ok(JsValue::UNDEFINED)
+        })
+    }
+
+    /// Whether the image `name` of a loaded manifest is available.
+    #[wasm_bindgen(js_name = hasImage)]
+    pub fn has_image(&self, name: &str) -> bool {
+        self.assets.borrow().image(name).is_some()
+    }
+
+    /// The image `name` of a loaded manifest, `undefined` until it is loaded.
+    pub fn image(&self, name: &str) -> Option<HtmlImageElement> {
+        self.assets.borrow().image(name).cloned()
+    }
 
-        self.keydown_handler.replace(keydown_handler);
-        self.keyup_handler.replace(keyup_handler);
+    fn dispatch_projection_change(&self) {
+        if let Some(event_target) = self.event_target.borrow().as_ref() {
+            event_target
+                .dispatch_event(&Event::new("projectionchange").unwrap_throw())
+                .unwrap_throw();
+        }
+    }
+
+    fn attach_event_handlers(&mut self) {
+        let window = window().unwrap_throw();
+        let document: Rc<dyn AsRef<EventTarget>> = Rc::new(window.document().unwrap_throw());
+        let window: Rc<dyn AsRef<EventTarget>> = Rc::new(window);
+        let canvas = &self.canvas;
+
+        self.input_handlers = vec![
+            self.input_handler(window.clone(), "keydown", |event| {
+                InputEvent::key_down(event.unchecked_ref())
+            }),
+            self.input_handler(window.clone(), "keyup", |event| {
+                InputEvent::key_up(event.unchecked_ref())
+            }),
+            EventHandler::new(window.clone(), "blur", {
+                let scenes = self.scenes.clone();
+                let renderer = self.renderer.clone();
+                let gamepads = self.gamepads.clone();
+
+                move |_| release_input(&scenes, &renderer, &gamepads)
+            }),
+            EventHandler::new(window.clone(), "gamepadconnected", {
+                let gamepads = self.gamepads.clone();
+
+                move |event| {
+                    let Some(gamepad) = event.unchecked_ref::<GamepadEvent>().gamepad() else {
+                        return;
+                    };
+
+                    if gamepad.mapping() != GamepadMappingType::Standard {
+                        log::warn!(
+                            "ignoring gamepad {}, it has no standard mapping",
+                            gamepad.id()
+                        );
+                    }
+
+                    gamepads.borrow_mut().connect(gamepad.index());
+                }
+            }),
+            EventHandler::new(window, "gamepaddisconnected", {
+                let gamepads = self.gamepads.clone();
+
+                move |event| {
+                    if let Some(gamepad) = event.unchecked_ref::<GamepadEvent>().gamepad() {
+                        gamepads.borrow_mut().disconnect(gamepad.index());
+                    }
+                }
+            }),
+            self.input_handler(canvas.clone(), "pointerdown", {
+                let canvas = canvas.clone();
+
+                move |event| {
+                    let event = event.unchecked_ref::<PointerEvent>();
+
+                    // keeps the moves and the release coming when dragging off the canvas
+                    canvas.set_pointer_capture(event.pointer_id()).ok();
+
+                    InputEvent::pointer_down(event, &canvas)
+                }
+            }),
+            self.input_handler(canvas.clone(), "pointerup", {
+                let canvas = canvas.clone();
+
+                move |event| InputEvent::pointer_up(event.unchecked_ref(), &canvas)
+            }),
+            self.input_handler(canvas.clone(), "pointermove", {
+                let canvas = canvas.clone();
+
+                move |event| InputEvent::pointer_move(event.unchecked_ref(), &canvas)
+            }),
+            self.input_handler(canvas.clone(), "pointercancel", {
+                let canvas = canvas.clone();
+
+                move |event| InputEvent::pointer_cancel(event.unchecked_ref(), &canvas)
+            }),
+            self.input_handler(canvas.clone(), "wheel", {
+                let canvas = canvas.clone();
+
+                move |event| InputEvent::wheel(event.unchecked_ref(), &canvas)
+            }),
+            self.input_handler(document, "pointerlockchange", {
+                let canvas = canvas.clone();
+
+                move |_| InputEvent::PointerLockChange {
+                    locked: is_pointer_locked(&canvas),
+                }
+            }),
+        ];
+    }
+
+    /// Forwards `type_` events to the top scene, preventing the default action of the ones it
+    /// uses. `input_event` may return `None` for events to ignore.
+    fn input_handler<I: Into<Option<InputEvent>>>(
+        &self,
+        target: Rc<dyn AsRef<EventTarget>>,
+        type_: &str,
+        input_event: impl Fn(&Event) -> I + 'static,
+    ) -> EventHandler {
+        let scenes = self.scenes.clone();
+        let renderer = self.renderer.clone();
+
+        EventHandler::new(target, type_, move |event| {
+            let Some(input_event) = input_event(event).into() else {
+                return;
+            };
+            let handled = scenes
+                .borrow_mut()
+                .handle_input(&input_event, &mut renderer.borrow_mut());
+
+            if handled {
+                event.prevent_default();
+            }
+        })
     }
 
     fn detach_event_handlers(&mut self) {
-        self.keydown_handler.take();
-        self.keyup_handler.take();
+        self.input_handlers.clear();
     }
 
     #[wasm_bindgen(skip_typescript, js_name = __postConstruct)]
@@ -156,12 +520,56 @@ impl Game {
     }
 }
 
+/// Scene stack, for games built on the crate rather than driven from JavaScript.
+impl Game {
+    pub fn push_scene(&self, scene: impl Scene + 'static) {
+        self.scenes
+            .borrow_mut()
+            .push(scene, &mut self.renderer.borrow_mut());
+    }
+
+    pub fn pop_scene(&self) {
+        self.scenes
+            .borrow_mut()
+            .pop(&mut self.renderer.borrow_mut());
+    }
+
+    pub fn replace_scene(&self, scene: impl Scene + 'static) {
+        self.scenes
+            .borrow_mut()
+            .replace(scene, &mut self.renderer.borrow_mut());
+    }
+
+    pub fn scenes(&self) -> &Rc<RefCell<SceneManager>> {
+        &self.scenes
+    }
+
+    /// Assets loaded by `loadAssets`, for scenes to keep and look up what they draw, e.g.
+    /// `assets.borrow().image("player")` for `Renderer::draw_sprite`.
+    pub fn assets(&self) -> &Rc<RefCell<Assets>> {
+        &self.assets
+    }
+}
+
 impl Drop for Game {
     fn drop(&mut self) {
         self.canvas.remove();
     }
 }
 
+/// Hands the top scene a `FocusLost`, which releases its gamepad input too, and has the next poll
+/// report the gamepad buttons and sticks still held again.
+fn release_input(
+    scenes: &RefCell<SceneManager>,
+    renderer: &RefCell<Renderer>,
+    gamepads: &RefCell<GamepadPoller>,
+) {
+    scenes
+        .borrow_mut()
+        .handle_input(&InputEvent::FocusLost, &mut renderer.borrow_mut());
+    gamepads.borrow_mut().reset();
+}
+
 fn create_canvas(container: Option<HtmlElement>) -> Result<HtmlCanvasElement, InitError> {
     let window = window().ok_or(InitError::NoWindow)?;
     let document = window.document().ok_or(InitError::NoDocument)?;
@@ -183,7 +591,59 @@ fn create_canvas(container: Option<HtmlElement>) -> Result<HtmlCanvasElement, In
     Ok(canvas)
 }
 
-fn request_animation_frame(callback: &Closure<dyn FnMut()>) -> i32 {
+fn dispatch_custom_event(
+    event_target: &RefCell<Option<EventTarget>>,
+    type_: &str,
+    detail: &JsValue,
+) {
+    if let Some(event_target) = event_target.borrow().as_ref() {
+        let init = CustomEventInit::new();
+
+        init.set_detail(detail);
+
+        event_target
+            .dispatch_event(&CustomEvent::new_with_event_init_dict(type_, &init).unwrap_throw())
+            .unwrap_throw();
+    }
+}
+
+fn progress_detail(progress: &AssetProgress) -> JsValue {
+    let detail = Object::new();
+
+    for (key, value) in [
+        ("name", JsValue::from_str(&progress.name)),
+        ("loaded", progress.loaded.into()),
+        ("failed", progress.failed.into()),
+        ("total", progress.total.into()),
+    ] {
+        Reflect::set(&detail, &key.into(), &value).unwrap_throw();
+    }
+
+    detail.into()
+}
+
+fn create_context(canvas: &HtmlCanvasElement) -> Result<CanvasRenderingContext2d, InitError> {
+    canvas
+        .get_context("2d")
+        .map_err(|err| InitError::ContextUnavailable(format!("{err:?}")))?
+        .ok_or_else(|| InitError::ContextUnavailable("2d context not supported".to_string()))?
+        .dyn_into::<CanvasRenderingContext2d>()
+        .map_err(|err| InitError::ContextUnavailable(format!("{err:?}")))
+}
+
+fn is_pointer_locked(canvas: &HtmlCanvasElement) -> bool {
+    window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.pointer_lock_element())
+        .is_some_and(|element| {
+            let element: &JsValue = element.as_ref();
+            let canvas: &JsValue = canvas.as_ref();
+
+            element == canvas
+        })
+}
+
+fn request_animation_frame(callback: &AnimationFrameCallback) -> i32 {
     window()
         .unwrap_throw()
         .request_animation_frame(callback.as_ref().unchecked_ref())
@@ -0,0 +1,389 @@
+//! Gamepads polled once per frame, their changes turned into `InputEvent`s.
+
+use std::collections::BTreeSet;
+
+use wasm_bindgen::JsCast;
+use web_sys::{window, Gamepad, GamepadButton, GamepadMappingType};
+
+use super::input::InputEvent;
+
+/// Buttons of the standard mapping, by position so they read the same on every controller.
+pub mod button {
+    pub const SOUTH: u32 = 0;
+    pub const EAST: u32 = 1;
+    pub const WEST: u32 = 2;
+    pub const NORTH: u32 = 3;
+    pub const LEFT_BUMPER: u32 = 4;
+    pub const RIGHT_BUMPER: u32 = 5;
+    pub const LEFT_TRIGGER: u32 = 6;
+    pub const RIGHT_TRIGGER: u32 = 7;
+    pub const SELECT: u32 = 8;
+    pub const START: u32 = 9;
+    pub const LEFT_STICK: u32 = 10;
+    pub const RIGHT_STICK: u32 = 11;
+    pub const DPAD_UP: u32 = 12;
+    pub const DPAD_DOWN: u32 = 13;
+    pub const DPAD_LEFT: u32 = 14;
+    pub const DPAD_RIGHT: u32 = 15;
+    pub const HOME: u32 = 16;
+}
+
+/// Axes of the standard mapping, between -1 and 1 with y pointing down.
+pub mod axis {
+    pub const LEFT_X: u32 = 0;
+    pub const LEFT_Y: u32 = 1;
+    pub const RIGHT_X: u32 = 2;
+    pub const RIGHT_Y: u32 = 3;
+}
+
+/// Stick tilt below which a stick reads as centered.
+pub const DEFAULT_DEADZONE: f64 = 0.15;
+
+/// State of one gamepad at a poll. Gamepads without the standard mapping are ignored, as their
+/// button and axis indices depend on the device.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GamepadSnapshot {
+    /// `Gamepad.index`, stable while the gamepad stays connected.
+    pub index: u32,
+    /// Whether the browser maps the gamepad to the standard layout.
+    pub standard: bool,
+    pub buttons: Vec<bool>,
+    pub axes: Vec<f64>,
+}
+
+impl GamepadSnapshot {
+    pub fn new(gamepad: &Gamepad) -> Self {
+        Self {
+            index: gamepad.index(),
+            standard: gamepad.mapping() == GamepadMappingType::Standard,
+            buttons: gamepad
+                .buttons()
+                .iter()
+                .map(|button| button.unchecked_into::<GamepadButton>().pressed())
+                .collect(),
+            axes: gamepad
+                .axes()
+                .iter()
+                .map(|axis| axis.as_f64().unwrap_or(0.0))
+                .collect(),
+        }
+    }
+}
+
+/// Merges the connected gamepads, a button is held while any gamepad holds it and an axis takes
+/// the largest tilt, and reports what changed since the previous poll. Gamepads are read once
+/// `connect` reported them, so that nothing is polled while none is plugged in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GamepadPoller {
+    deadzone: f64,
+    /// `Gamepad.index` of the connected gamepads.
+    connected: BTreeSet<u32>,
+    buttons: Vec<bool>,
+    axes: Vec<f64>,
+}
+
+impl Default for GamepadPoller {
+    fn default() -> Self {
+        Self {
+            deadzone: DEFAULT_DEADZONE,
+            connected: BTreeSet::new(),
+            buttons: Vec::new(),
+            axes: Vec::new(),
+        }
+    }
+}
+
+impl GamepadPoller {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn deadzone(&self) -> f64 {
+        self.deadzone
+    }
+
+    /// `deadzone` is clamped between 0 and 1.
+    pub fn set_deadzone(&mut self, deadzone: f64) {
+        self.deadzone = deadzone.clamp(0.0, 1.0);
+    }
+
+    /// Forgets the last poll, the buttons and sticks still held are reported again by the next one.
+    pub fn reset(&mut self) {
+        self.buttons.clear();
+        self.axes.clear();
+    }
+
+    /// Starts reading the gamepad at `index`, on `gamepadconnected`.
+    pub fn connect(&mut self, index: u32) {
+        self.connected.insert(index);
+    }
+
+    /// Stops reading the gamepad at `index`, on `gamepaddisconnected`. What it held is released
+    /// by the next poll.
+    pub fn disconnect(&mut self, index: u32) {
+        self.connected.remove(&index);
+    }
+
+    /// Connects the gamepads already plugged in, whose `gamepadconnected` came before the
+    /// listener was added.
+    pub fn connect_present(&mut self) {
+        for gamepad in gamepads() {
+            self.connect(gamepad.index());
+        }
+    }
+
+    /// Reads `navigator.getGamepads()`, call it once per frame.
+    pub fn poll(&mut self) -> Vec<InputEvent> {
+        let gamepads: Vec<_> = if self.connected.is_empty() {
+            Vec::new()
+        } else {
+            gamepads()
+                .map(|gamepad| GamepadSnapshot::new(&gamepad))
+                .collect()
+        };
+
+        self.update(&gamepads)
+    }
+
+    /// Returns the button and axis changes of `gamepads` since the previous update. Gamepads
+    /// missing from `gamepads`, not connected or without the standard mapping hold nothing.
+    pub fn update(&mut self, gamepads: &[GamepadSnapshot]) -> Vec<InputEvent> {
+        let mut buttons = Vec::new();
+        let mut axes: Vec<f64> = Vec::new();
+
+        for gamepad in gamepads
+            .iter()
+            .filter(|gamepad| gamepad.standard && self.connected.contains(&gamepad.index))
+        {
+            merge(&mut buttons, &gamepad.buttons, |held, pressed| {
+                *held |= pressed
+            });
+            merge(
+                &mut axes,
+                &apply_deadzone(&gamepad.axes, self.deadzone),
+                |value, tilt| {
+                    if tilt.abs() > value.abs() {
+                        *value = tilt;
+                    }
+                },
+            );
+        }
+
+        let mut events = Vec::new();
+
+        for index in 0..buttons.len().max(self.buttons.len()) {
+            let (was, is) = (
+                self.buttons.get(index).copied().unwrap_or_default(),
+                buttons.get(index).copied().unwrap_or_default(),
+            );
+            let button = index as u32;
+
+            match (was, is) {
+                (false, true) => events.push(InputEvent::GamepadButtonDown { button }),
+                (true, false) => events.push(InputEvent::GamepadButtonUp { button }),
+                _ => {}
+            }
+        }
+
+        for index in 0..axes.len().max(self.axes.len()) {
+            let value = axes.get(index).copied().unwrap_or_default();
+
+            if self.axes.get(index).copied().unwrap_or_default() != value {
+                events.push(InputEvent::GamepadAxis {
+                    axis: index as u32,
+                    value,
+                });
+            }
+        }
+
+        self.buttons = buttons;
+        self.axes = axes;
+
+        events
+    }
+}
+
+fn gamepads() -> impl Iterator<Item = Gamepad> {
+    window()
+        .and_then(|window| window.navigator().get_gamepads().ok())
+        .into_iter()
+        .flat_map(|gamepads| gamepads.to_vec())
+        .filter_map(|gamepad| gamepad.dyn_into::<Gamepad>().ok())
+        .filter(Gamepad::connected)
+}
+
+fn merge<T: Copy + Default>(merged: &mut Vec<T>, values: &[T], f: impl Fn(&mut T, T)) {
+    if merged.len() < values.len() {
+        merged.resize(values.len(), T::default());
+    }
+
+    for (merged, value) in merged.iter_mut().zip(values) {
+        f(merged, *value);
+    }
+}
+
+/// Zeroes the tilts below `deadzone` and rescales the others to still span 0 to 1. The axes are
+/// taken in pairs, as the x and y of a stick, so that diagonals are not cut short.
+pub fn apply_deadzone(axes: &[f64], deadzone: f64) -> Vec<f64> {
+    axes.chunks(2)
+        .flat_map(|stick| {
+            let length = stick.iter().map(|value| value * value).sum::<f64>().sqrt();
+            let scale = if length <= deadzone || deadzone >= 1.0 {
+                0.0
+            } else {
+                ((length - deadzone) / (1.0 - deadzone)).min(1.0) / length
+            };
+
+            stick.iter().map(move |value| value * scale)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(index: u32, buttons: &[bool], axes: &[f64]) -> GamepadSnapshot {
+        GamepadSnapshot {
+            index,
+            standard: true,
+            buttons: buttons.to_vec(),
+            axes: axes.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_apply_deadzone() {
+        assert_eq!(
+            apply_deadzone(&[0.1, -0.1, 0.0, 1.0], 0.2),
+            [0.0, 0.0, 0.0, 1.0]
+        );
+        assert_eq!(apply_deadzone(&[0.625, 0.0], 0.25), [0.5, 0.0]);
+
+        let [x, y] = apply_deadzone(&[0.8, 0.8], 0.2)[..] else {
+            panic!("two axes");
+        };
+
+        // a stick pushed fully into a corner reads as a unit tilt along the diagonal
+        assert!((x.hypot(y) - 1.0).abs() < 1e-9);
+        assert!((x - y).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_buttons() {
+        let mut poller = GamepadPoller::new();
+
+        poller.connect(0);
+        poller.connect(1);
+
+        assert_eq!(
+            poller.update(&[snapshot(0, &[true, false], &[])]),
+            [InputEvent::GamepadButtonDown {
+                button: button::SOUTH
+            }]
+        );
+        assert!(poller
+            .update(&[snapshot(0, &[true, false], &[])])
+            .is_empty());
+
+        // a second gamepad holding the same button keeps it held
+        assert_eq!(
+            poller.update(&[
+                snapshot(0, &[false, false], &[]),
+                snapshot(1, &[true, true], &[]),
+            ]),
+            [InputEvent::GamepadButtonDown {
+                button: button::EAST
+            }]
+        );
+
+        // disconnecting releases what it held
+        assert_eq!(
+            poller.update(&[]),
+            [
+                InputEvent::GamepadButtonUp {
+                    button: button::SOUTH
+                },
+                InputEvent::GamepadButtonUp {
+                    button: button::EAST
+                },
+            ]
+        );
+
+        poller.update(&[snapshot(0, &[true], &[])]);
+        poller.reset();
+
+        assert_eq!(
+            poller.update(&[snapshot(0, &[true], &[])]),
+            [InputEvent::GamepadButtonDown {
+                button: button::SOUTH
+            }]
+        );
+    }
+
+    #[test]
+    fn test_axes() {
+        let mut poller = GamepadPoller::new();
+
+        poller.connect(0);
+        poller.connect(1);
+        poller.set_deadzone(0.2);
+
+        // drift inside the deadzone is not reported
+        assert!(poller.update(&[snapshot(0, &[], &[0.1, 0.05])]).is_empty());
+        assert_eq!(
+            poller.update(&[
+                snapshot(0, &[], &[0.6, 0.0]),
+                snapshot(1, &[], &[-1.0, 0.0])
+            ]),
+            [InputEvent::GamepadAxis {
+                axis: axis::LEFT_X,
+                value: -1.0,
+            }]
+        );
+        assert_eq!(
+            poller.update(&[]),
+            [InputEvent::GamepadAxis {
+                axis: axis::LEFT_X,
+                value: 0.0,
+            }]
+        );
+
+        poller.set_deadzone(2.0);
+
+        assert_eq!(poller.deadzone(), 1.0);
+    }
+
+    #[test]
+    fn test_connected_standard_gamepads_only() {
+        let mut poller = GamepadPoller::new();
+        let other = GamepadSnapshot {
+            standard: false,
+            ..snapshot(1, &[true], &[])
+        };
+
+        poller.connect(0);
+
+        // not reported by gamepadconnected
+        assert!(poller.update(&[snapshot(2, &[true], &[])]).is_empty());
+        // a device specific layout
+        poller.connect(1);
+        assert!(poller.update(&[other]).is_empty());
+
+        assert_eq!(
+            poller.update(&[snapshot(0, &[true], &[])]),
+            [InputEvent::GamepadButtonDown {
+                button: button::SOUTH
+            }]
+        );
+
+        poller.disconnect(0);
+
+        assert_eq!(
+            poller.update(&[snapshot(0, &[true], &[])]),
+            [InputEvent::GamepadButtonUp {
+                button: button::SOUTH
+            }]
+        );
+    }
+}
@@ -21,7 +21,7 @@ impl EventHandler {
         target
             .as_ref()
             .as_ref()
-            .add_event_listener_with_callback(&type_, listener.as_ref().unchecked_ref())
+            .add_event_listener_with_callback(type_, listener.as_ref().unchecked_ref())
             .unwrap_throw();
 
         Self {
@@ -0,0 +1,841 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+use web_sys::{HtmlCanvasElement, KeyboardEvent, PointerEvent, WheelEvent};
+
+use super::{
+    backend::Rect,
+    touch::{Gesture, GestureKind, GesturePhase},
+};
+
+/// Input handed to scenes, copied out of the DOM events so scenes do not depend on them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputEvent {
+    KeyDown {
+        key: String,
+        code: String,
+        /// Sent again by the key held down, at the keyboard repeat rate.
+        repeat: bool,
+    },
+    KeyUp {
+        key: String,
+        code: String,
+    },
+    PointerDown {
+        pointer: Pointer,
+        /// `PointerEvent.button`, `0` is the primary button.
+        button: i16,
+    },
+    PointerUp {
+        pointer: Pointer,
+        button: i16,
+    },
+    PointerMove {
+        pointer: Pointer,
+        /// Screen pixels since the last move, the only position change reported under pointer
+        /// lock.
+        movement_x: f64,
+        movement_y: f64,
+        /// Pressed buttons as a bitmask, `1` is the primary button.
+        buttons: u16,
+    },
+    /// The browser took the pointer over, as when a touch turns into a page scroll.
+    PointerCancel {
+        pointer: Pointer,
+    },
+    /// Scroll in canvas pixels, positive `delta_y` scrolls down.
+    Wheel {
+        delta_x: f64,
+        delta_y: f64,
+    },
+    PointerLockChange {
+        locked: bool,
+    },
+    /// Button of the standard gamepad mapping.
+    GamepadButtonDown {
+        button: u32,
+    },
+    GamepadButtonUp {
+        button: u32,
+    },
+    /// Axis of the standard gamepad mapping, past the deadzone, with sticks pointing down at 1.
+    GamepadAxis {
+        axis: u32,
+        value: f64,
+    },
+    /// The window lost the focus, keys released meanwhile send no `KeyUp`.
+    FocusLost,
+}
+
+/// CSS pixels scrolled per line by wheels reporting lines.
+const WHEEL_LINE_HEIGHT: f64 = 16.0;
+
+impl InputEvent {
+    pub fn key_down(event: &KeyboardEvent) -> Self {
+        Self::KeyDown {
+            key: event.key(),
+            code: event.code(),
+            repeat: event.repeat(),
+        }
+    }
+
+    pub fn key_up(event: &KeyboardEvent) -> Self {
+        Self::KeyUp {
+            key: event.key(),
+            code: event.code(),
+        }
+    }
+
+    /// Pointer of the pointer events.
+    pub fn pointer(&self) -> Option<&Pointer> {
+        match self {
+            Self::PointerDown { pointer, .. }
+            | Self::PointerUp { pointer, .. }
+            | Self::PointerMove { pointer, .. }
+            | Self::PointerCancel { pointer } => Some(pointer),
+            _ => None,
+        }
+    }
+
+    pub fn pointer_down(event: &PointerEvent, canvas: &HtmlCanvasElement) -> Self {
+        Self::PointerDown {
+            pointer: Pointer::new(event, canvas),
+            button: event.button(),
+        }
+    }
+
+    pub fn pointer_up(event: &PointerEvent, canvas: &HtmlCanvasElement) -> Self {
+        Self::PointerUp {
+            pointer: Pointer::new(event, canvas),
+            button: event.button(),
+        }
+    }
+
+    pub fn pointer_move(event: &PointerEvent, canvas: &HtmlCanvasElement) -> Self {
+        Self::PointerMove {
+            pointer: Pointer::new(event, canvas),
+            movement_x: event.movement_x() as f64,
+            movement_y: event.movement_y() as f64,
+            buttons: event.buttons(),
+        }
+    }
+
+    pub fn pointer_cancel(event: &PointerEvent, canvas: &HtmlCanvasElement) -> Self {
+        Self::PointerCancel {
+            pointer: Pointer::new(event, canvas),
+        }
+    }
+
+    pub fn wheel(event: &WheelEvent, canvas: &HtmlCanvasElement) -> Self {
+        let (bounds, size) = canvas_layout(canvas);
+        let (delta_x, delta_y) = wheel_to_canvas(
+            (event.delta_x(), event.delta_y()),
+            event.delta_mode(),
+            bounds,
+            size,
+        );
+
+        Self::Wheel { delta_x, delta_y }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerKind {
+    Mouse,
+    Pen,
+    Touch,
+}
+
+impl PointerKind {
+    /// Parses `PointerEvent.pointerType`, unknown types are treated as a mouse.
+    pub fn from_pointer_type(pointer_type: &str) -> Self {
+        match pointer_type {
+            "pen" => PointerKind::Pen,
+            "touch" => PointerKind::Touch,
+            _ => PointerKind::Mouse,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pointer {
+    /// `PointerEvent.pointerId`, stable while the pointer is down.
+    pub id: i32,
+    pub kind: PointerKind,
+    /// Position in canvas pixels.
+    pub x: f64,
+    pub y: f64,
+    /// `Event.timeStamp` in milliseconds.
+    pub time: f64,
+}
+
+impl Pointer {
+    pub fn new(event: &PointerEvent, canvas: &HtmlCanvasElement) -> Self {
+        let (bounds, size) = canvas_layout(canvas);
+        let (x, y) = client_to_canvas(
+            (event.client_x() as f64, event.client_y() as f64),
+            bounds,
+            size,
+        );
+
+        Self {
+            id: event.pointer_id(),
+            kind: PointerKind::from_pointer_type(&event.pointer_type()),
+            x,
+            y,
+            time: event.time_stamp(),
+        }
+    }
+}
+
+/// Client rectangle of `canvas` and its size in canvas pixels.
+fn canvas_layout(canvas: &HtmlCanvasElement) -> (Rect, (f64, f64)) {
+    let bounds = canvas.get_bounding_client_rect();
+
+    (
+        Rect::new(bounds.left(), bounds.top(), bounds.width(), bounds.height()),
+        (canvas.width() as f64, canvas.height() as f64),
+    )
+}
+
+/// Converts a client position to canvas pixels, for a canvas of `size` pixels laid out in `bounds`
+/// by CSS. Borders and padding are assumed to be zero.
+pub fn client_to_canvas((x, y): (f64, f64), bounds: Rect, size: (f64, f64)) -> (f64, f64) {
+    let scale = |length: f64, css_length: f64| {
+        if css_length > 0.0 {
+            length / css_length
+        } else {
+            1.0
+        }
+    };
+
+    (
+        (x - bounds.x) * scale(size.0, bounds.width),
+        (y - bounds.y) * scale(size.1, bounds.height),
+    )
+}
+
+/// Converts wheel deltas counted in `delta_mode` units to canvas pixels, for a canvas laid out as
+/// for [`client_to_canvas`]. A page is the CSS size of the canvas.
+pub fn wheel_to_canvas(
+    (x, y): (f64, f64),
+    delta_mode: u32,
+    bounds: Rect,
+    size: (f64, f64),
+) -> (f64, f64) {
+    let (unit_x, unit_y) = match delta_mode {
+        WheelEvent::DOM_DELTA_LINE => (WHEEL_LINE_HEIGHT, WHEEL_LINE_HEIGHT),
+        WheelEvent::DOM_DELTA_PAGE => (bounds.width, bounds.height),
+        _ => (1.0, 1.0),
+    };
+
+    client_to_canvas(
+        (x * unit_x, y * unit_y),
+        Rect::new(0.0, 0.0, bounds.width, bounds.height),
+        size,
+    )
+}
+
+/// Physical input an action can be bound to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Binding {
+    /// `KeyboardEvent.code`, the key position whatever the keyboard layout.
+    Key(String),
+    /// `PointerEvent.button` of a mouse or pen, `0` is the primary button.
+    MouseButton(i16),
+    GamepadButton(u32),
+    /// Held while a drag, pinch or rotate gesture goes on, taps press and release at once.
+    Gesture(GestureKind),
+}
+
+impl Binding {
+    pub fn key(code: &str) -> Self {
+        Self::Key(code.to_string())
+    }
+}
+
+/// Input reporting a value between -1 and 1 rather than held or not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AnalogInput {
+    /// Virtual joystick knob, positive to the right.
+    JoystickX,
+    /// Virtual joystick knob, positive upwards.
+    JoystickY,
+    /// Axis of the standard gamepad mapping, see `gamepad::axis`.
+    GamepadAxis(u32),
+    /// Gamepad axis pointing the other way, as a stick y axis for an axis positive upwards.
+    GamepadAxisInverted(u32),
+}
+
+/// Bindings pushing an axis towards -1 and 1, it rests at 0 when both or neither are held. The
+/// analog inputs set the axis while none of the bindings is held.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AxisBinding {
+    pub negative: Vec<Binding>,
+    pub positive: Vec<Binding>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub analog: Vec<AnalogInput>,
+}
+
+/// Maps bindings to named actions and axes, and tracks which bindings are held.
+///
+/// ```json
+/// {
+///   "actions": { "jump": [{ "key": "Space" }, { "gamepadButton": 0 }] },
+///   "axes": { "move_x": { "negative": [{ "key": "KeyA" }], "positive": [{ "key": "KeyD" }] } }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputMap {
+    actions: BTreeMap<String, Vec<Binding>>,
+    axes: BTreeMap<String, AxisBinding>,
+    #[serde(skip)]
+    held: HashSet<Binding>,
+    #[serde(skip)]
+    pressed: HashSet<Binding>,
+    #[serde(skip)]
+    released: HashSet<Binding>,
+    #[serde(skip)]
+    analog: HashMap<AnalogInput, f64>,
+}
+
+impl InputMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `binding` to the bindings of `action`.
+    pub fn bind(&mut self, action: &str, binding: Binding) -> &mut Self {
+        let bindings = self.actions.entry(action.to_string()).or_default();
+
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+
+        self
+    }
+
+    pub fn bind_axis(
+        &mut self,
+        axis: &str,
+        negative: impl IntoIterator<Item = Binding>,
+        positive: impl IntoIterator<Item = Binding>,
+    ) -> &mut Self {
+        self.axes.insert(
+            axis.to_string(),
+            AxisBinding {
+                negative: negative.into_iter().collect(),
+                positive: positive.into_iter().collect(),
+                analog: Vec::new(),
+            },
+        );
+        self
+    }
+
+    /// Adds `input` to the analog inputs of `axis`.
+    pub fn bind_analog(&mut self, axis: &str, input: AnalogInput) -> &mut Self {
+        let analog = &mut self.axes.entry(axis.to_string()).or_default().analog;
+
+        if !analog.contains(&input) {
+            analog.push(input);
+        }
+
+        self
+    }
+
+    /// Replaces the bindings of `action`.
+    pub fn rebind(&mut self, action: &str, bindings: impl IntoIterator<Item = Binding>) {
+        self.actions
+            .insert(action.to_string(), bindings.into_iter().collect());
+    }
+
+    pub fn unbind(&mut self, action: &str, binding: &Binding) -> bool {
+        let Some(bindings) = self.actions.get_mut(action) else {
+            return false;
+        };
+        let len = bindings.len();
+
+        bindings.retain(|b| b != binding);
+        bindings.len() != len
+    }
+
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.actions.get(action).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn axis_binding(&self, axis: &str) -> Option<&AxisBinding> {
+        self.axes.get(axis)
+    }
+
+    /// Takes the bindings of `other`, releasing everything held.
+    pub fn set_bindings(&mut self, other: InputMap) {
+        self.actions = other.actions;
+        self.axes = other.axes;
+        self.release_all();
+    }
+
+    /// Whether `binding` is used by an action or an axis.
+    pub fn is_bound(&self, binding: &Binding) -> bool {
+        self.actions.values().flatten().any(|b| b == binding)
+            || self
+                .axes
+                .values()
+                .flat_map(|axis| axis.negative.iter().chain(&axis.positive))
+                .any(|b| b == binding)
+    }
+
+    /// Records the binding of `event`, returns whether it is bound.
+    pub fn handle(&mut self, event: &InputEvent) -> bool {
+        let (binding, held) = match event {
+            // the key is already held
+            InputEvent::KeyDown {
+                code, repeat: true, ..
+            } => return self.is_bound(&Binding::Key(code.clone())),
+            InputEvent::KeyDown { code, .. } => (Binding::Key(code.clone()), true),
+            InputEvent::KeyUp { code, .. } => (Binding::Key(code.clone()), false),
+            InputEvent::PointerDown { pointer, button } if pointer.kind != PointerKind::Touch => {
+                (Binding::MouseButton(*button), true)
+            }
+            InputEvent::PointerUp { pointer, button } if pointer.kind != PointerKind::Touch => {
+                (Binding::MouseButton(*button), false)
+            }
+            InputEvent::GamepadButtonDown { button } => (Binding::GamepadButton(*button), true),
+            InputEvent::GamepadButtonUp { button } => (Binding::GamepadButton(*button), false),
+            InputEvent::GamepadAxis { axis, value } => {
+                self.set_analog(AnalogInput::GamepadAxis(*axis), *value);
+
+                return self.is_analog_bound(*axis);
+            }
+            InputEvent::FocusLost => {
+                self.release_all();
+
+                return false;
+            }
+            _ => return false,
+        };
+        let bound = self.is_bound(&binding);
+
+        self.set_held(binding, held);
+
+        bound
+    }
+
+    /// Records the binding of `gesture`, returns whether it is bound.
+    pub fn handle_gesture(&mut self, gesture: &Gesture) -> bool {
+        let binding = Binding::Gesture(gesture.kind());
+        let bound = self.is_bound(&binding);
+
+        match gesture.phase() {
+            Some(GesturePhase::Start) => self.set_held(binding, true),
+            Some(GesturePhase::Change) => {}
+            Some(GesturePhase::End) => self.set_held(binding, false),
+            None => {
+                self.set_held(binding.clone(), true);
+                self.set_held(binding, false);
+            }
+        }
+
+        bound
+    }
+
+    pub fn set_held(&mut self, binding: Binding, held: bool) {
+        if held {
+            if self.held.insert(binding.clone()) {
+                self.pressed.insert(binding);
+            }
+        } else if self.held.remove(&binding) {
+            self.released.insert(binding);
+        }
+    }
+
+    /// `value` is clamped between -1 and 1.
+    pub fn set_analog(&mut self, input: AnalogInput, value: f64) {
+        self.analog.insert(input, value.clamp(-1.0, 1.0));
+    }
+
+    /// Releases every binding and recenters the analog inputs.
+    pub fn release_all(&mut self) {
+        self.released.extend(self.held.drain());
+        self.analog.clear();
+    }
+
+    pub fn is_pressed(&self, action: &str) -> bool {
+        self.bindings(action).iter().any(|b| self.held.contains(b))
+    }
+
+    /// Whether `action` was pressed since the last `end_update`, even if already released.
+    pub fn just_pressed(&self, action: &str) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|b| self.pressed.contains(b))
+    }
+
+    pub fn just_released(&self, action: &str) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|b| self.released.contains(b))
+    }
+
+    /// Value of `axis` between -1 and 1, 0 for unknown axes.
+    pub fn axis(&self, axis: &str) -> f64 {
+        let Some(axis) = self.axes.get(axis) else {
+            return 0.0;
+        };
+        let held = |bindings: &[Binding]| bindings.iter().any(|b| self.held.contains(b));
+
+        match (held(&axis.negative), held(&axis.positive)) {
+            (true, false) => -1.0,
+            (false, true) => 1.0,
+            _ => axis
+                .analog
+                .iter()
+                .map(|input| self.analog_value(*input))
+                .fold(
+                    0.0,
+                    |value: f64, v| {
+                        if v.abs() > value.abs() {
+                            v
+                        } else {
+                            value
+                        }
+                    },
+                ),
+        }
+    }
+
+    fn analog_value(&self, input: AnalogInput) -> f64 {
+        match input {
+            AnalogInput::GamepadAxisInverted(axis) => {
+                -self.analog_value(AnalogInput::GamepadAxis(axis))
+            }
+            input => self.analog.get(&input).copied().unwrap_or_default(),
+        }
+    }
+
+    fn is_analog_bound(&self, gamepad_axis: u32) -> bool {
+        self.axes
+            .values()
+            .flat_map(|axis| &axis.analog)
+            .any(|input| match input {
+                AnalogInput::GamepadAxis(axis) | AnalogInput::GamepadAxisInverted(axis) => {
+                    *axis == gamepad_axis
+                }
+                _ => false,
+            })
+    }
+
+    /// Forgets the presses and releases seen so far, call it at the end of each update.
+    pub fn end_update(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("bindings serialize to JSON")
+    }
+}
+
+impl FromStr for InputMap {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_down(code: &str) -> InputEvent {
+        InputEvent::KeyDown {
+            key: String::new(),
+            code: code.to_string(),
+            repeat: false,
+        }
+    }
+
+    fn key_up(code: &str) -> InputEvent {
+        InputEvent::KeyUp {
+            key: String::new(),
+            code: code.to_string(),
+        }
+    }
+
+    fn pointer(kind: PointerKind) -> Pointer {
+        Pointer {
+            id: 1,
+            kind,
+            x: 0.0,
+            y: 0.0,
+            time: 0.0,
+        }
+    }
+
+    fn pointer_down(kind: PointerKind) -> InputEvent {
+        InputEvent::PointerDown {
+            pointer: pointer(kind),
+            button: 0,
+        }
+    }
+
+    fn input_map() -> InputMap {
+        let mut input_map = InputMap::new();
+
+        input_map
+            .bind("jump", Binding::key("Space"))
+            .bind("jump", Binding::GamepadButton(0))
+            .bind("fire", Binding::MouseButton(0))
+            .bind_axis(
+                "move_x",
+                [Binding::key("KeyA"), Binding::key("ArrowLeft")],
+                [Binding::key("KeyD"), Binding::key("ArrowRight")],
+            );
+
+        input_map
+    }
+
+    #[test]
+    fn test_actions() {
+        let mut input_map = input_map();
+
+        assert!(input_map.handle(&InputEvent::GamepadButtonDown { button: 0 }));
+        assert!(input_map.is_pressed("jump"));
+        assert!(input_map.just_pressed("jump"));
+
+        input_map.end_update();
+
+        assert!(input_map.is_pressed("jump"));
+        assert!(!input_map.just_pressed("jump"));
+        assert!(input_map.handle(&pointer_down(PointerKind::Mouse)));
+        assert!(input_map.handle(&InputEvent::PointerUp {
+            pointer: pointer(PointerKind::Mouse),
+            button: 0,
+        }));
+
+        // pressed and released between two updates
+        assert!(!input_map.is_pressed("fire"));
+        assert!(input_map.just_pressed("fire"));
+        assert!(input_map.just_released("fire"));
+        assert!(!input_map.handle(&key_down("KeyQ")));
+        assert!(!input_map.is_pressed("unknown"));
+
+        // touches are gestures rather than buttons
+        assert!(!input_map.handle(&pointer_down(PointerKind::Touch)));
+        assert!(!input_map.is_pressed("fire"));
+    }
+
+    #[test]
+    fn test_client_to_canvas() {
+        // a 480x360 canvas stretched to 960x540 CSS pixels at (100, 50)
+        let bounds = Rect::new(100.0, 50.0, 960.0, 540.0);
+
+        assert_eq!(
+            client_to_canvas((100.0, 50.0), bounds, (480.0, 360.0)),
+            (0.0, 0.0)
+        );
+        assert_eq!(
+            client_to_canvas((580.0, 320.0), bounds, (480.0, 360.0)),
+            (240.0, 180.0)
+        );
+        assert_eq!(
+            client_to_canvas((10.0, 20.0), Rect::new(0.0, 0.0, 0.0, 0.0), (480.0, 360.0)),
+            (10.0, 20.0)
+        );
+    }
+
+    #[test]
+    fn test_wheel_to_canvas() {
+        // a 480x360 canvas stretched to 960x540 CSS pixels
+        let bounds = Rect::new(100.0, 50.0, 960.0, 540.0);
+        let size = (480.0, 360.0);
+
+        assert_eq!(
+            wheel_to_canvas((10.0, -90.0), WheelEvent::DOM_DELTA_PIXEL, bounds, size),
+            (5.0, -60.0)
+        );
+        assert_eq!(
+            wheel_to_canvas((0.0, 3.0), WheelEvent::DOM_DELTA_LINE, bounds, size),
+            (0.0, 32.0)
+        );
+        assert_eq!(
+            wheel_to_canvas((1.0, 1.0), WheelEvent::DOM_DELTA_PAGE, bounds, size),
+            size
+        );
+    }
+
+    #[test]
+    fn test_axis() {
+        let mut input_map = input_map();
+
+        assert_eq!(input_map.axis("move_x"), 0.0);
+
+        input_map.handle(&key_down("KeyA"));
+
+        assert_eq!(input_map.axis("move_x"), -1.0);
+
+        input_map.handle(&key_down("ArrowRight"));
+
+        assert_eq!(input_map.axis("move_x"), 0.0);
+
+        input_map.handle(&key_up("KeyA"));
+
+        assert_eq!(input_map.axis("move_x"), 1.0);
+
+        input_map.release_all();
+
+        assert_eq!(input_map.axis("move_x"), 0.0);
+        assert_eq!(input_map.axis("unknown"), 0.0);
+    }
+
+    #[test]
+    fn test_analog_axis() {
+        let mut input_map = input_map();
+
+        input_map.bind_analog("move_x", AnalogInput::JoystickX);
+        input_map.set_analog(AnalogInput::JoystickX, -0.5);
+        input_map.set_analog(AnalogInput::JoystickY, 1.0);
+
+        assert_eq!(input_map.axis("move_x"), -0.5);
+
+        // held bindings win over the joystick
+        input_map.handle(&key_down("KeyD"));
+
+        assert_eq!(input_map.axis("move_x"), 1.0);
+
+        input_map.release_all();
+        input_map.set_analog(AnalogInput::JoystickX, 3.0);
+
+        assert_eq!(input_map.axis("move_x"), 1.0);
+        assert!(input_map.to_json().contains(r#""analog":["joystickX"]"#));
+    }
+
+    #[test]
+    fn test_gamepad_axis() {
+        let mut input_map = input_map();
+
+        input_map
+            .bind_analog("move_x", AnalogInput::GamepadAxis(0))
+            .bind_analog("move_y", AnalogInput::GamepadAxisInverted(1));
+
+        assert!(input_map.handle(&InputEvent::GamepadAxis {
+            axis: 1,
+            value: 0.25,
+        }));
+        assert!(!input_map.handle(&InputEvent::GamepadAxis {
+            axis: 2,
+            value: 1.0,
+        }));
+        assert_eq!(input_map.axis("move_y"), -0.25);
+        assert_eq!(input_map.axis("move_x"), 0.0);
+        assert!(input_map
+            .to_json()
+            .contains(r#""analog":[{"gamepadAxisInverted":1}]"#));
+    }
+
+    #[test]
+    fn test_gestures() {
+        let mut input_map = input_map();
+        let drag = |phase| Gesture::Drag {
+            phase,
+            dx: 0.0,
+            dy: 0.0,
+        };
+
+        input_map
+            .bind("select", Binding::Gesture(GestureKind::Tap))
+            .bind("pan", Binding::Gesture(GestureKind::Drag));
+
+        assert!(input_map.handle_gesture(&Gesture::Tap { x: 1.0, y: 2.0 }));
+        assert!(input_map.just_pressed("select"));
+        assert!(input_map.just_released("select"));
+        assert!(!input_map.is_pressed("select"));
+
+        input_map.handle_gesture(&drag(GesturePhase::Start));
+        input_map.handle_gesture(&drag(GesturePhase::Change));
+
+        assert!(input_map.is_pressed("pan"));
+
+        input_map.handle_gesture(&drag(GesturePhase::End));
+
+        assert!(!input_map.is_pressed("pan"));
+        assert!(!input_map.handle_gesture(&Gesture::DoubleTap { x: 0.0, y: 0.0 }));
+    }
+
+    #[test]
+    fn test_repeat_and_focus_loss() {
+        let mut input_map = input_map();
+
+        input_map.handle(&key_down("Space"));
+        input_map.end_update();
+
+        assert!(input_map.handle(&InputEvent::KeyDown {
+            key: " ".to_string(),
+            code: "Space".to_string(),
+            repeat: true,
+        }));
+        assert!(input_map.is_pressed("jump"));
+        assert!(!input_map.just_pressed("jump"));
+
+        input_map.handle(&InputEvent::FocusLost);
+
+        assert!(!input_map.is_pressed("jump"));
+        assert!(input_map.just_released("jump"));
+
+        // a repeat arriving after the focus came back does not press the key again
+        input_map.end_update();
+        input_map.handle(&InputEvent::KeyDown {
+            key: " ".to_string(),
+            code: "Space".to_string(),
+            repeat: true,
+        });
+
+        assert!(!input_map.is_pressed("jump"));
+    }
+
+    #[test]
+    fn test_rebind() {
+        let mut input_map = input_map();
+
+        input_map.rebind("jump", [Binding::key("KeyW")]);
+
+        assert!(!input_map.handle(&key_down("Space")));
+        assert!(input_map.handle(&key_down("KeyW")));
+        assert!(input_map.is_pressed("jump"));
+        assert!(input_map.unbind("jump", &Binding::key("KeyW")));
+        assert!(!input_map.is_pressed("jump"));
+        assert!(input_map.bindings("jump").is_empty());
+    }
+
+    #[test]
+    fn test_json() {
+        let mut input_map = input_map();
+
+        input_map.handle(&key_down("Space"));
+
+        let json = input_map.to_json();
+        let parsed: InputMap = json.parse().unwrap();
+
+        assert!(json.contains(r#""jump":[{"key":"Space"},{"gamepadButton":0}]"#));
+        assert_eq!(parsed.bindings("jump"), input_map.bindings("jump"));
+        assert_eq!(
+            parsed.axis_binding("move_x"),
+            input_map.axis_binding("move_x")
+        );
+        assert!(!parsed.is_pressed("jump"));
+
+        let parsed: InputMap = r#"{ "actions": { "fire": [{ "mouseButton": 2 }] } }"#
+            .parse()
+            .unwrap();
+
+        assert_eq!(parsed.bindings("fire"), [Binding::MouseButton(2)]);
+        assert!(r#"{ "actions": { "fire": [{ "pedal": 1 }] } }"#.parse::<InputMap>().is_err());
+    }
+}
@@ -0,0 +1,74 @@
+use super::math::Vec3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DirectionalLight {
+    /// Direction the light travels in, does not need to be normalized.
+    pub direction: Vec3,
+    pub color: [f64; 3],
+    /// Fraction of the color applied to faces turned away from the light.
+    pub ambient: f64,
+}
+
+impl DirectionalLight {
+    /// Lambert diffuse term for a surface facing `normal`, channels are in `[0, 1]`.
+    pub fn lambert(&self, normal: Vec3, albedo: [f64; 3]) -> [f64; 3] {
+        let intensity = normal
+            .normalize()
+            .dot(&-self.direction.normalize())
+            .max(0.0);
+        let mut color = [0.0; 3];
+
+        for (i, channel) in color.iter_mut().enumerate() {
+            let light = self.ambient + (1.0 - self.ambient) * intensity * self.color[i];
+
+            *channel = (albedo[i] * light).clamp(0.0, 1.0);
+        }
+
+        color
+    }
+}
+
+impl Default for DirectionalLight {
+    fn default() -> Self {
+        Self {
+            direction: Vec3::new(1.0, 1.0, 2.0),
+            color: [1.0; 3],
+            ambient: 0.2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lambert() {
+        let light = DirectionalLight {
+            direction: Vec3::new(0.0, 0.0, 1.0),
+            color: [1.0, 0.5, 1.0],
+            ambient: 0.0,
+        };
+
+        assert_eq!(
+            light.lambert(Vec3::new(0.0, 0.0, -2.0), [1.0, 1.0, 0.5]),
+            [1.0, 0.5, 0.5]
+        );
+        assert_eq!(
+            light.lambert(Vec3::new(0.0, 0.0, 1.0), [1.0, 1.0, 1.0]),
+            [0.0, 0.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn test_ambient() {
+        let light = DirectionalLight {
+            direction: Vec3::new(0.0, -1.0, 0.0),
+            color: [1.0; 3],
+            ambient: 0.25,
+        };
+
+        assert_eq!(light.lambert(Vec3::new(1.0, 0.0, 0.0), [1.0; 3]), [0.25; 3]);
+        assert_eq!(light.lambert(Vec3::new(0.0, 1.0, 0.0), [0.5; 3]), [0.5; 3]);
+    }
+}
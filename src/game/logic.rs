@@ -1,164 +1,875 @@
-use std::str::FromStr;
+use std::rc::Rc;
 
-use js_sys::Date;
-use thiserror::Error;
-use web_sys::KeyboardEvent;
+use super::{
+    animation::AnimationPlayer,
+    backend::RenderBackend,
+    camera::{Camera, CameraController, CameraInput, CameraMode},
+    ecs::{self, Entity, MeshRenderer, PreviousTransform, Schedule, Transform, Velocity, World},
+    gamepad::{axis, button},
+    input::{AnalogInput, Binding, InputEvent, InputMap, PointerKind},
+    math::Vec3,
+    mesh::Mesh,
+    renderer::Renderer,
+    scene::{Scene, Transitions},
+    touch::{Gesture, GestureKind, GestureRecognizer, VirtualJoystick},
+};
 
-use super::{math::Vec4, renderer::Renderer};
+/// Radians per second the cube turns while an arrow key is held.
+const ROTATION_SPEED: f64 = 6.0;
 
-#[derive(Debug, Default)]
-pub struct GameLogic {
-    last_frame_time: f64,
-    rotate_x: f64,
-    rotate_y: f64,
-    rotation_x: f64,
-    rotation_y: f64,
-    rotation_z: f64,
-}
+/// Radians the cube turns per canvas pixel dragged.
+const DRAG_SPEED: f64 = 0.01;
+
+/// Orbit distance per wheel pixel.
+const WHEEL_ZOOM: f64 = 0.005;
+
+/// Orbit distance zoomed when the fingers of a pinch spread twice as far apart.
+const PINCH_ZOOM: f64 = 1.0;
 
+/// The spinning cube demo. Its state is advanced in fixed steps by `update`, `render` draws it
+/// interpolated between the last two steps.
 #[derive(Debug)]
-enum ControlKey {
-    ArrowUp,
-    ArrowDown,
-    ArrowLeft,
-    ArrowRight,
+pub struct GameLogic {
+    input_map: InputMap,
+    world: World,
+    schedule: Schedule,
+    cube: Option<Entity>,
+    camera: Camera,
+    previous_camera: Camera,
+    camera_mode: CameraMode,
+    camera_controller: CameraController,
+    camera_input: CameraInput,
+    /// Last position of the mouse or pen, in canvas pixels.
+    pointer: Option<(f64, f64)>,
+    pointer_locked: bool,
+    /// Canvas pixels dragged since the last update.
+    drag: (f64, f64),
+    /// Orbit distance scrolled or pinched since the last update.
+    zoom: f64,
+    gestures: GestureRecognizer,
+    /// Radians the cube was twisted clockwise with two fingers since the last update.
+    twist: f64,
+    joystick: Option<VirtualJoystick>,
+    animations: AnimationPlayer,
 }
 
-#[derive(Debug, Error)]
-enum ControlKeyError {
-    #[error("not a control key")]
-    NotAControlKey,
-}
+impl Default for GameLogic {
+    fn default() -> Self {
+        let mut input_map = InputMap::new();
 
-impl FromStr for ControlKey {
-    type Err = ControlKeyError;
+        input_map
+            .bind("toggle_camera", Binding::key("KeyC"))
+            .bind("toggle_camera", Binding::Gesture(GestureKind::DoubleTap))
+            .bind("toggle_camera", Binding::GamepadButton(button::NORTH))
+            .bind("grab", Binding::MouseButton(0))
+            .bind("grab", Binding::Gesture(GestureKind::Drag))
+            .bind("zoom", Binding::Gesture(GestureKind::Pinch))
+            .bind("twist", Binding::Gesture(GestureKind::Rotate))
+            .bind_axis(
+                "rotate_x",
+                [Binding::key("ArrowLeft")],
+                [Binding::key("ArrowRight")],
+            )
+            .bind_analog("rotate_x", AnalogInput::JoystickX)
+            .bind_analog("rotate_x", AnalogInput::GamepadAxis(axis::LEFT_X))
+            .bind_axis(
+                "rotate_y",
+                [Binding::key("ArrowDown")],
+                [Binding::key("ArrowUp")],
+            )
+            .bind_analog("rotate_y", AnalogInput::JoystickY)
+            .bind_analog("rotate_y", AnalogInput::GamepadAxisInverted(axis::LEFT_Y))
+            .bind_axis("move_x", [Binding::key("KeyA")], [Binding::key("KeyD")])
+            .bind_axis("move_z", [Binding::key("KeyS")], [Binding::key("KeyW")]);
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "ArrowUp" => Ok(ControlKey::ArrowUp),
-            "ArrowDown" => Ok(ControlKey::ArrowDown),
-            "ArrowLeft" => Ok(ControlKey::ArrowLeft),
-            "ArrowRight" => Ok(ControlKey::ArrowRight),
-            _ => Err(ControlKeyError::NotAControlKey),
+        Self {
+            input_map,
+            world: World::default(),
+            schedule: Schedule::default(),
+            cube: None,
+            camera: Camera::default(),
+            previous_camera: Camera::default(),
+            camera_mode: CameraMode::default(),
+            camera_controller: CameraController::default(),
+            camera_input: CameraInput::default(),
+            pointer: None,
+            pointer_locked: false,
+            drag: (0.0, 0.0),
+            zoom: 0.0,
+            gestures: GestureRecognizer::default(),
+            twist: 0.0,
+            joystick: None,
+            animations: AnimationPlayer::default(),
         }
     }
 }
 
 impl GameLogic {
-    pub fn on_keydown(&mut self, event: &KeyboardEvent) {
-        if let Ok(key) = event.key().parse::<ControlKey>() {
-            event.prevent_default();
+    pub fn input_map(&self) -> &InputMap {
+        &self.input_map
+    }
 
-            match key {
-                ControlKey::ArrowUp => {
-                    self.rotate_y = 1.0;
-                }
-                ControlKey::ArrowDown => {
-                    self.rotate_y = -1.0;
-                }
-                ControlKey::ArrowLeft => {
-                    self.rotate_x = -1.0;
-                }
-                ControlKey::ArrowRight => {
-                    self.rotate_x = 1.0;
-                }
+    pub fn input_map_mut(&mut self) -> &mut InputMap {
+        &mut self.input_map
+    }
+
+    /// Shows or hides the on-screen joystick turning the cube like the arrow keys.
+    pub fn set_virtual_joystick(&mut self, joystick: Option<VirtualJoystick>) {
+        self.joystick = joystick;
+        self.input_map.set_analog(AnalogInput::JoystickX, 0.0);
+        self.input_map.set_analog(AnalogInput::JoystickY, 0.0);
+    }
+
+    pub fn virtual_joystick(&self) -> Option<&VirtualJoystick> {
+        self.joystick.as_ref()
+    }
+
+    pub fn set_camera_mode(&mut self, camera_mode: CameraMode) {
+        self.camera_mode = camera_mode;
+    }
+
+    pub fn camera_mode(&self) -> CameraMode {
+        self.camera_mode
+    }
+
+    /// Moves the camera and rebuilds the controller around it, the orbit camera keeps looking at
+    /// its target.
+    pub fn set_camera_position<B: RenderBackend>(
+        &mut self,
+        renderer: &mut Renderer<B>,
+        position: Vec3,
+    ) {
+        let target = match &self.camera_controller {
+            CameraController::Orbit(orbit) if self.camera_mode == CameraMode::Orbit => {
+                Some(orbit.target)
             }
+            _ => None,
+        };
+
+        self.camera.position = position;
+
+        if let Some(target) = target {
+            self.camera.look_at(target);
         }
+
+        self.previous_camera = self.camera;
+        self.camera_controller = CameraController::new(self.camera_mode, &self.camera);
+
+        if let (Some(target), CameraController::Orbit(orbit)) =
+            (target, &mut self.camera_controller)
+        {
+            orbit.target = target;
+            orbit.distance = (target - position).length();
+        }
+
+        self.sync_camera(renderer, 1.0);
     }
 
-    pub fn on_keyup(&mut self, event: &KeyboardEvent) {
-        if let Ok(key) = event.key().parse::<ControlKey>() {
-            event.prevent_default();
+    fn handle_gesture(&mut self, gesture: &Gesture) -> bool {
+        let bound = self.input_map.handle_gesture(gesture);
 
-            match key {
-                ControlKey::ArrowUp => {
-                    self.rotate_y = 0.0;
-                }
-                ControlKey::ArrowDown => {
-                    self.rotate_y = 0.0;
-                }
-                ControlKey::ArrowLeft => {
-                    self.rotate_x = 0.0;
+        match *gesture {
+            Gesture::Drag { dx, dy, .. } if self.input_map.is_pressed("grab") => {
+                self.drag = (self.drag.0 + dx, self.drag.1 + dy);
+            }
+            Gesture::Pinch { scale, .. } if self.input_map.is_pressed("zoom") => {
+                self.zoom += scale.log2() * PINCH_ZOOM;
+            }
+            Gesture::Rotate { angle, .. } if self.input_map.is_pressed("twist") => {
+                self.twist += angle;
+            }
+            _ => {}
+        }
+
+        bound
+    }
+
+    fn sync_camera<B: RenderBackend>(&self, renderer: &mut Renderer<B>, alpha: f64) {
+        let (previous, current) = (&self.previous_camera, &self.camera);
+
+        renderer.update_camera(|camera| {
+            camera.position = previous.position.lerp(&current.position, alpha);
+            camera.orientation = previous.orientation.slerp(&current.orientation, alpha);
+        });
+    }
+}
+
+impl<B: RenderBackend> Scene<B> for GameLogic
+where
+    B::Image: 'static,
+{
+    fn setup(&mut self, renderer: &mut Renderer<B>) {
+        self.input_map.release_all();
+        self.input_map.end_update();
+        self.world.clear();
+        self.cube = Some(self.world.spawn((
+            Transform::default(),
+            PreviousTransform::default(),
+            Velocity::default(),
+            MeshRenderer::new(Rc::new(Mesh::cube(1.0)), [1.0, 0.75, 0.8]),
+        )));
+        self.schedule = Schedule::new();
+        self.schedule
+            .add_system("store_previous_transforms", ecs::store_previous_transforms)
+            .add_system("apply_velocity", ecs::apply_velocity);
+        self.previous_camera = self.camera;
+        self.camera_input = CameraInput::default();
+        self.drag = (0.0, 0.0);
+        self.zoom = 0.0;
+        self.gestures.reset();
+        self.twist = 0.0;
+
+        if let Some(joystick) = &mut self.joystick {
+            joystick.reset();
+        }
+
+        self.sync_camera(renderer, 1.0);
+    }
+
+    fn update(&mut self, dt: f64, _transitions: &mut Transitions<B>) {
+        if self.input_map.just_pressed("toggle_camera") {
+            self.set_camera_mode(match self.camera_mode() {
+                CameraMode::Orbit => CameraMode::FreeFly,
+                CameraMode::FreeFly => CameraMode::Orbit,
+            });
+        }
+
+        if self.camera_controller.mode() != self.camera_mode {
+            self.camera_controller = CameraController::new(self.camera_mode, &self.camera);
+        }
+
+        self.camera_input.movement = Vec3::new(
+            self.input_map.axis("move_x"),
+            0.0,
+            self.input_map.axis("move_z"),
+        );
+        self.camera_input.zoom = self.zoom / dt;
+        self.zoom = 0.0;
+        self.previous_camera = self.camera;
+        self.camera_controller
+            .update(&mut self.camera, &self.camera_input, dt);
+
+        self.camera_input.look = (0.0, 0.0);
+        self.camera_input.zoom = 0.0;
+
+        self.animations.update(dt);
+
+        if let Some(velocity) = self
+            .cube
+            .and_then(|cube| self.world.get_mut::<Velocity>(cube))
+        {
+            // the drag and the twist are turned into the spin reaching them within the step
+            velocity.angular = Vec3::new(
+                self.input_map.axis("rotate_y"),
+                self.input_map.axis("rotate_x"),
+                0.0,
+            ) * ROTATION_SPEED
+                + Vec3::new(self.drag.1, self.drag.0, 0.0) * (DRAG_SPEED / dt)
+                + Vec3::new(0.0, 0.0, -self.twist / dt);
+        }
+
+        self.drag = (0.0, 0.0);
+        self.twist = 0.0;
+
+        self.schedule.run(&mut self.world, dt);
+        self.input_map.end_update();
+    }
+
+    fn render(&mut self, renderer: &mut Renderer<B>, alpha: f64) {
+        self.sync_camera(renderer, alpha);
+
+        ecs::render(&self.world, renderer, alpha);
+
+        if let Some(joystick) = &self.joystick {
+            joystick.draw(renderer);
+        }
+    }
+
+    fn handle_input(&mut self, event: &InputEvent, _transitions: &mut Transitions<B>) -> bool {
+        if let Some(joystick) = &mut self.joystick {
+            if joystick.handle(event) {
+                let (x, y) = joystick.value();
+
+                self.input_map.set_analog(AnalogInput::JoystickX, x);
+                self.input_map.set_analog(AnalogInput::JoystickY, y);
+
+                return true;
+            }
+        }
+
+        let mut handled = self.input_map.handle(event);
+
+        for gesture in self.gestures.handle(event) {
+            handled |= self.handle_gesture(&gesture);
+        }
+
+        // keeps the browser from following touches with emulated mouse events
+        handled |= event
+            .pointer()
+            .is_some_and(|pointer| pointer.kind == PointerKind::Touch);
+
+        match event {
+            InputEvent::PointerDown { pointer, .. } if pointer.kind != PointerKind::Touch => {
+                self.pointer = Some((pointer.x, pointer.y));
+            }
+            InputEvent::PointerMove {
+                pointer,
+                movement_x,
+                movement_y,
+                ..
+            } if pointer.kind != PointerKind::Touch => {
+                if self.pointer_locked {
+                    let (x, y) = self.camera_input.look;
+
+                    self.camera_input.look = (x + movement_x, y + movement_y);
+                } else if let (Some((x, y)), true) =
+                    (self.pointer, self.input_map.is_pressed("grab"))
+                {
+                    self.drag = (self.drag.0 + pointer.x - x, self.drag.1 + pointer.y - y);
                 }
-                ControlKey::ArrowRight => {
-                    self.rotate_x = 0.0;
+
+                self.pointer = Some((pointer.x, pointer.y));
+            }
+            InputEvent::Wheel { delta_y, .. } => {
+                self.zoom -= delta_y * WHEEL_ZOOM;
+
+                return true;
+            }
+            InputEvent::PointerLockChange { locked } => {
+                self.pointer_locked = *locked;
+            }
+            // touches and buttons released while away are never reported
+            InputEvent::FocusLost => {
+                self.pointer = None;
+                self.gestures.reset();
+
+                if let Some(joystick) = &mut self.joystick {
+                    joystick.reset();
                 }
             }
+            _ => {}
         }
+
+        handled
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::super::{
+        animation::{Animation, PlaybackMode},
+        backend::{Color, DrawCommand, MemoryBackend},
+        gamepad::{GamepadPoller, GamepadSnapshot},
+        input::Pointer,
+        scene::SceneManager,
+    };
+    use super::*;
 
-    pub fn setup(&mut self) {
-        self.last_frame_time = Date::now();
-        self.rotate_x = 0.0;
-        self.rotate_y = 0.0;
-        self.rotation_x = 0.0;
-        self.rotation_y = 0.0;
-        self.rotation_z = 0.0;
+    fn pointer(x: f64, y: f64) -> Pointer {
+        Pointer {
+            id: 1,
+            kind: PointerKind::Mouse,
+            x,
+            y,
+            time: 0.0,
+        }
     }
 
-    pub fn draw(&mut self, renderer: &mut Renderer) {
-        let _delta = self.compute_delta();
+    fn touch(id: i32, x: f64, y: f64) -> Pointer {
+        Pointer {
+            id,
+            kind: PointerKind::Touch,
+            ..pointer(x, y)
+        }
+    }
 
-        if self.rotate_x != 0.0 && self.rotate_y != 0.0 {
-            self.rotation_z += 0.1 * self.rotate_y;
-        } else if self.rotate_x != 0.0 {
-            self.rotation_x += 0.1 * self.rotate_x;
-        } else if self.rotate_y != 0.0 {
-            self.rotation_y += 0.1 * self.rotate_y;
+    /// Moves the pointer to `(10 + dx, 10 + dy)`, with a movement of `(dx, dy)`.
+    fn pointer_move(dx: f64, dy: f64) -> InputEvent {
+        InputEvent::PointerMove {
+            pointer: pointer(10.0 + dx, 10.0 + dy),
+            movement_x: dx,
+            movement_y: dy,
+            buttons: 1,
         }
+    }
+
+    fn start(
+        game_logic: GameLogic,
+        renderer: &mut Renderer<MemoryBackend>,
+    ) -> (Rc<RefCell<GameLogic>>, SceneManager<MemoryBackend>) {
+        let game_logic = Rc::new(RefCell::new(game_logic));
+        let mut scenes = SceneManager::new();
+
+        scenes.push(game_logic.clone(), renderer);
+
+        (game_logic, scenes)
+    }
+
+    #[test]
+    fn test_draw() {
+        let mut renderer = Renderer::new(MemoryBackend::new(48, 36));
+        let (_, mut scenes) = start(GameLogic::default(), &mut renderer);
+
+        scenes.frame(&mut renderer, 0.0);
+        scenes.frame(&mut renderer, 16.0);
+
+        let commands = &renderer.backend().commands;
+
+        assert_eq!(commands.first(), Some(&DrawCommand::Clear(Color::BLACK)));
+        assert_eq!(commands.last(), Some(&DrawCommand::Present));
+        assert!(renderer.backend().triangles().count() > 0);
+        assert_ne!(renderer.backend().pixel(24, 18), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_set_camera_position() {
+        let mut renderer = Renderer::new(MemoryBackend::new(48, 36));
+        let (game_logic, mut scenes) = start(GameLogic::default(), &mut renderer);
+
+        game_logic
+            .borrow_mut()
+            .set_camera_position(&mut renderer, Vec3::new(0.0, 0.0, -5.0));
+        scenes.frame(&mut renderer, 0.0);
 
-        renderer.clear_frame();
+        let position = renderer.camera().position;
 
-        let points = [
-            (0, 0, 0),
-            (1, 0, 0),
-            (1, 1, 0),
-            (0, 1, 0),
-            (0, 0, 1),
-            (1, 0, 1),
-            (1, 1, 1),
-            (0, 1, 1),
-        ];
+        assert!((position - Vec3::new(0.0, 0.0, -5.0)).length() < 1e-9);
 
-        for (i, point) in points.into_iter().enumerate() {
-            renderer.point(&point, self.rotation_x, self.rotation_y, self.rotation_z);
+        game_logic
+            .borrow_mut()
+            .set_camera_position(&mut renderer, Vec3::new(4.0, 0.0, 0.0));
+        scenes.frame(&mut renderer, 16.0);
+
+        let camera = renderer.camera();
+
+        assert!((camera.position - Vec3::new(4.0, 0.0, 0.0)).length() < 1e-9);
+        assert!((camera.forward() - Vec3::new(-1.0, 0.0, 0.0)).length() < 1e-9);
+    }
+
+    #[test]
+    fn test_draw_advances_animations() {
+        let mut game_logic = GameLogic::default();
+        let mut renderer = Renderer::new(MemoryBackend::new(48, 36));
+
+        game_logic
+            .animations
+            .add_state(
+                "idle",
+                Animation::uniform(["a", "b"], 0.1, PlaybackMode::Loop),
+            )
+            .unwrap();
+        game_logic.animations.play("idle").unwrap();
+
+        let (game_logic, mut scenes) = start(game_logic, &mut renderer);
+
+        for time in [1000.0, 1050.0, 1100.0, 1150.0] {
+            scenes.frame(&mut renderer, time);
         }
 
-        for i in 0..4 {
-            renderer.line(
-                &points[i],
-                &points[(i + 1) % 4],
-                self.rotation_x,
-                self.rotation_y,
-                self.rotation_z,
-            );
-            renderer.line(
-                &points[i + 4],
-                &points[(i + 1) % 4 + 4],
-                self.rotation_x,
-                self.rotation_y,
-                self.rotation_z,
-            );
-            renderer.line(
-                &points[i],
-                &points[i + 4],
-                self.rotation_x,
-                self.rotation_y,
-                self.rotation_z,
-            );
+        assert_eq!(
+            game_logic
+                .borrow()
+                .animations
+                .frame()
+                .map(|f| f.name.as_str()),
+            Some("b")
+        );
+    }
+
+    #[test]
+    fn test_rotation_does_not_depend_on_refresh_rate() {
+        let angle = |hz: f64| {
+            let mut renderer = Renderer::new(MemoryBackend::new(8, 6));
+            let (game_logic, mut scenes) = start(GameLogic::default(), &mut renderer);
+
+            game_logic
+                .borrow_mut()
+                .input_map
+                .set_held(Binding::key("ArrowRight"), true);
+
+            for frame in 0..=hz as u32 {
+                scenes.frame(&mut renderer, frame as f64 * 1000.0 / hz);
+            }
+
+            let game_logic = game_logic.borrow();
+            let cube = game_logic.cube.unwrap();
+            let rotation = game_logic.world.get::<Transform>(cube).unwrap().rotation;
+
+            rotation.w().acos() * 2.0
+        };
+
+        assert!((angle(60.0) - angle(144.0)).abs() <= ROTATION_SPEED / 60.0 + 1e-9);
+        assert!((angle(60.0) - ROTATION_SPEED % std::f64::consts::TAU).abs() < 0.2);
+    }
+
+    #[test]
+    fn test_render_interpolates() {
+        let mut game_logic = GameLogic::default();
+        let mut renderer = Renderer::new(MemoryBackend::new(8, 6));
+
+        game_logic.setup(&mut renderer);
+        game_logic.set_camera_mode(CameraMode::FreeFly);
+        game_logic.input_map.set_held(Binding::key("KeyW"), true);
+        game_logic.update(0.5, &mut Transitions::<MemoryBackend>::default());
+
+        let (previous, current) = (game_logic.previous_camera, game_logic.camera);
+
+        game_logic.render(&mut renderer, 0.5);
+
+        let halfway = previous.position.lerp(&current.position, 0.5);
+
+        assert!((renderer.camera().position - halfway).length() < 1e-9);
+        assert!((current.position - previous.position).length() > 0.5);
+    }
+
+    #[test]
+    fn test_handle_input() {
+        let mut renderer = Renderer::new(MemoryBackend::new(8, 6));
+        let (game_logic, mut scenes) = start(GameLogic::default(), &mut renderer);
+        let key_down = |code: &str| InputEvent::KeyDown {
+            key: String::new(),
+            code: code.to_string(),
+            repeat: false,
+        };
+
+        assert!(scenes.handle_input(&key_down("ArrowRight"), &mut renderer));
+        assert_eq!(game_logic.borrow().input_map.axis("rotate_x"), 1.0);
+
+        assert!(scenes.handle_input(
+            &InputEvent::KeyUp {
+                key: String::new(),
+                code: "ArrowRight".to_string(),
+            },
+            &mut renderer
+        ));
+        assert_eq!(game_logic.borrow().input_map.axis("rotate_x"), 0.0);
+        assert!(!scenes.handle_input(&key_down("KeyQ"), &mut renderer));
+
+        assert!(!scenes.handle_input(&pointer_move(3.0, -2.0), &mut renderer));
+        assert_eq!(game_logic.borrow().camera_input.look, (0.0, 0.0));
+
+        scenes.handle_input(
+            &InputEvent::PointerLockChange { locked: true },
+            &mut renderer,
+        );
+        scenes.handle_input(&pointer_move(3.0, -2.0), &mut renderer);
+
+        assert_eq!(game_logic.borrow().camera_input.look, (3.0, -2.0));
+    }
+
+    #[test]
+    fn test_drag_rotates_cube() {
+        let mut renderer = Renderer::new(MemoryBackend::new(8, 6));
+        let (game_logic, mut scenes) = start(GameLogic::default(), &mut renderer);
+
+        scenes.frame(&mut renderer, 0.0);
+        scenes.handle_input(
+            &InputEvent::PointerDown {
+                pointer: pointer(10.0, 10.0),
+                button: 0,
+            },
+            &mut renderer,
+        );
+        scenes.handle_input(&pointer_move(20.0, 0.0), &mut renderer);
+        scenes.handle_input(&pointer_move(30.0, 0.0), &mut renderer);
+        scenes.frame(&mut renderer, 20.0);
+        scenes.frame(&mut renderer, 40.0);
+
+        let game_logic = game_logic.borrow();
+        let rotation = game_logic
+            .world
+            .get::<Transform>(game_logic.cube.unwrap())
+            .unwrap()
+            .rotation;
+
+        // 30 pixels around the vertical axis
+        assert!((rotation.w().acos() * 2.0 - 30.0 * DRAG_SPEED).abs() < 1e-9);
+        assert!(rotation.y() > 0.0);
+    }
+
+    #[test]
+    fn test_wheel_zooms() {
+        let mut renderer = Renderer::new(MemoryBackend::new(8, 6));
+        let (_, mut scenes) = start(GameLogic::default(), &mut renderer);
+
+        scenes.frame(&mut renderer, 0.0);
+        scenes.frame(&mut renderer, 20.0);
+
+        let distance = renderer.camera().position.length();
+
+        assert!(scenes.handle_input(
+            &InputEvent::Wheel {
+                delta_x: 0.0,
+                delta_y: -100.0,
+            },
+            &mut renderer
+        ));
+        scenes.frame(&mut renderer, 40.0);
+        scenes.frame(&mut renderer, 60.0);
+
+        let zoomed = renderer.camera().position.length();
+
+        assert!((distance - zoomed - 100.0 * WHEEL_ZOOM).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_toggle_camera() {
+        let mut renderer = Renderer::new(MemoryBackend::new(8, 6));
+        let (game_logic, mut scenes) = start(GameLogic::default(), &mut renderer);
+        let key = |code: &str| (String::new(), code.to_string());
+
+        scenes.frame(&mut renderer, 0.0);
+
+        // a tap shorter than a step still toggles once
+        let (key_name, code) = key("KeyC");
+
+        scenes.handle_input(
+            &InputEvent::KeyDown {
+                key: key_name.clone(),
+                code: code.clone(),
+                repeat: false,
+            },
+            &mut renderer,
+        );
+        scenes.handle_input(
+            &InputEvent::KeyUp {
+                key: key_name,
+                code,
+            },
+            &mut renderer,
+        );
+        scenes.frame(&mut renderer, 20.0);
+        scenes.frame(&mut renderer, 40.0);
+
+        assert_eq!(game_logic.borrow().camera_mode(), CameraMode::FreeFly);
+    }
+
+    #[test]
+    fn test_pinch_zooms() {
+        let mut renderer = Renderer::new(MemoryBackend::new(8, 6));
+        let (_, mut scenes) = start(GameLogic::default(), &mut renderer);
+        let touch_move = |id, x, y| InputEvent::PointerMove {
+            pointer: touch(id, x, y),
+            movement_x: 0.0,
+            movement_y: 0.0,
+            buttons: 1,
+        };
+
+        scenes.frame(&mut renderer, 0.0);
+        scenes.frame(&mut renderer, 20.0);
+
+        let distance = renderer.camera().position.length();
+
+        for (id, x) in [(1, 0.0), (2, 10.0)] {
+            assert!(scenes.handle_input(
+                &InputEvent::PointerDown {
+                    pointer: touch(id, x, 0.0),
+                    button: 0,
+                },
+                &mut renderer
+            ));
+        }
+
+        // spread twice as far apart, in two moves
+        scenes.handle_input(&touch_move(2, 15.0, 0.0), &mut renderer);
+        scenes.handle_input(&touch_move(2, 20.0, 0.0), &mut renderer);
+        scenes.frame(&mut renderer, 40.0);
+        scenes.frame(&mut renderer, 60.0);
+
+        let zoomed = renderer.camera().position.length();
+
+        assert!((distance - zoomed - PINCH_ZOOM).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_virtual_joystick() {
+        let mut renderer = Renderer::new(MemoryBackend::new(48, 36));
+        let (game_logic, mut scenes) = start(GameLogic::default(), &mut renderer);
+        let joystick = VirtualJoystick::new((10.0, 10.0), 5.0);
+
+        game_logic
+            .borrow_mut()
+            .set_virtual_joystick(Some(joystick.clone()));
+
+        // pushed all the way to the right, like holding the right arrow
+        assert!(scenes.handle_input(
+            &InputEvent::PointerDown {
+                pointer: touch(1, 12.0, 10.0),
+                button: 0,
+            },
+            &mut renderer
+        ));
+        assert!(scenes.handle_input(
+            &InputEvent::PointerMove {
+                pointer: touch(1, 20.0, 10.0),
+                movement_x: 8.0,
+                movement_y: 0.0,
+                buttons: 1,
+            },
+            &mut renderer
+        ));
+        assert_eq!(game_logic.borrow().input_map.axis("rotate_x"), 1.0);
+        assert_eq!(game_logic.borrow().gestures.touches(), 0);
+
+        scenes.frame(&mut renderer, 0.0);
+
+        assert!(renderer.backend().commands.iter().any(|command| matches!(
+            command,
+            DrawCommand::Point { x, radius, .. } if *x == 15.0 && *radius == 2.0
+        )));
+
+        scenes.handle_input(
+            &InputEvent::PointerUp {
+                pointer: touch(1, 20.0, 10.0),
+                button: 0,
+            },
+            &mut renderer,
+        );
+
+        assert_eq!(game_logic.borrow().input_map.axis("rotate_x"), 0.0);
+
+        game_logic.borrow_mut().set_virtual_joystick(None);
+        renderer.backend_mut().commands.clear();
+        scenes.frame(&mut renderer, 20.0);
+
+        assert!(!renderer
+            .backend()
+            .commands
+            .iter()
+            .any(|command| matches!(command, DrawCommand::Point { .. })));
+    }
+
+    #[test]
+    fn test_left_stick_rotates_cube() {
+        let mut renderer = Renderer::new(MemoryBackend::new(8, 6));
+        let (game_logic, mut scenes) = start(GameLogic::default(), &mut renderer);
+        let mut poller = GamepadPoller::new();
+
+        poller.connect(0);
+
+        // pushed fully up
+        for event in poller.update(&[GamepadSnapshot {
+            standard: true,
+            axes: vec![0.0, -1.0, 0.0, 0.0],
+            ..Default::default()
+        }]) {
+            assert!(scenes.handle_input(&event, &mut renderer));
+        }
+
+        let input_map = &game_logic.borrow().input_map;
+
+        assert_eq!(input_map.axis("rotate_x"), 0.0);
+        assert_eq!(input_map.axis("rotate_y"), 1.0);
+    }
+
+    #[test]
+    fn test_stick_held_through_focus_loss() {
+        let mut renderer = Renderer::new(MemoryBackend::new(8, 6));
+        let (game_logic, mut scenes) = start(GameLogic::default(), &mut renderer);
+        let mut poller = GamepadPoller::new();
+        let held = [GamepadSnapshot {
+            standard: true,
+            axes: vec![-1.0, 0.0],
+            ..Default::default()
+        }];
+        let rotate_x = || game_logic.borrow().input_map.axis("rotate_x");
+
+        poller.connect(0);
+
+        for event in poller.update(&held) {
+            scenes.handle_input(&event, &mut renderer);
+        }
+
+        // as on blur, the scene lets go and the poller reports the stick again
+        scenes.handle_input(&InputEvent::FocusLost, &mut renderer);
+        poller.reset();
+
+        assert_eq!(rotate_x(), 0.0);
+
+        for event in poller.update(&held) {
+            scenes.handle_input(&event, &mut renderer);
         }
+
+        assert_eq!(rotate_x(), -1.0);
+    }
+
+    #[test]
+    fn test_held_keys_do_not_cancel() {
+        let mut renderer = Renderer::new(MemoryBackend::new(8, 6));
+        let (game_logic, mut scenes) = start(GameLogic::default(), &mut renderer);
+        let key = |code: &str, down: bool| {
+            let (key, code) = (String::new(), code.to_string());
+
+            if down {
+                InputEvent::KeyDown {
+                    key,
+                    code,
+                    repeat: false,
+                }
+            } else {
+                InputEvent::KeyUp { key, code }
+            }
+        };
+        let rotate_x = || game_logic.borrow().input_map.axis("rotate_x");
+
+        scenes.handle_input(&key("ArrowLeft", true), &mut renderer);
+        scenes.handle_input(&key("ArrowRight", true), &mut renderer);
+        scenes.handle_input(&key("ArrowRight", false), &mut renderer);
+
+        assert_eq!(rotate_x(), -1.0);
+
+        // the repeats of the key still held change nothing
+        assert!(scenes.handle_input(
+            &InputEvent::KeyDown {
+                key: String::new(),
+                code: "ArrowLeft".to_string(),
+                repeat: true,
+            },
+            &mut renderer
+        ));
+        assert_eq!(rotate_x(), -1.0);
+
+        // alt-tabbing away with the key down must not leave it held
+        scenes.handle_input(&InputEvent::FocusLost, &mut renderer);
+
+        assert_eq!(rotate_x(), 0.0);
     }
 
-    pub fn is_running(&self) -> bool {
-        return true;
+    /// Pause menu over the cube, using none of the input.
+    struct Overlay;
+
+    impl Scene<MemoryBackend> for Overlay {
+        fn update(&mut self, _dt: f64, _transitions: &mut Transitions<MemoryBackend>) {}
+
+        fn render(&mut self, _renderer: &mut Renderer<MemoryBackend>, _alpha: f64) {}
+
+        fn is_overlay(&self) -> bool {
+            true
+        }
     }
 
-    fn compute_delta(&mut self) -> f64 {
-        let frame_time = Date::now();
-        let delta = frame_time - self.last_frame_time;
+    #[test]
+    fn test_covered_scene_releases_held_keys() {
+        let mut renderer = Renderer::new(MemoryBackend::new(8, 6));
+        let (game_logic, mut scenes) = start(GameLogic::default(), &mut renderer);
+        let (key, code) = (String::new(), "ArrowLeft".to_string());
+
+        scenes.handle_input(
+            &InputEvent::KeyDown {
+                key: key.clone(),
+                code: code.clone(),
+                repeat: false,
+            },
+            &mut renderer,
+        );
+        scenes.push(Overlay, &mut renderer);
 
-        self.last_frame_time = frame_time;
+        // released while the overlay has the input
+        scenes.handle_input(&InputEvent::KeyUp { key, code }, &mut renderer);
+        scenes.pop(&mut renderer);
 
-        delta
+        assert_eq!(game_logic.borrow().input_map.axis("rotate_x"), 0.0);
     }
 }
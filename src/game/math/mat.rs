@@ -2,12 +2,88 @@ use std::ops::Mul;
 
 use super::Vec4;
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub struct Mat4 {
     pub value: [[f64; 4]; 4],
 }
 
-impl Mat4 {}
+impl Mat4 {
+    pub fn identity() -> Self {
+        Self {
+            value: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut value = [[0.0; 4]; 4];
+
+        for (i, row) in value.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                *cell = self.value[j][i];
+            }
+        }
+
+        Self { value }
+    }
+
+    pub fn determinant(&self) -> f64 {
+        (0..4)
+            .map(|j| {
+                let sign = if j % 2 == 0 { 1.0 } else { -1.0 };
+                sign * self.value[0][j] * self.minor(0, j)
+            })
+            .sum()
+    }
+
+    /// Returns `None` when the matrix is singular, that is when its determinant is negligible
+    /// next to the product of its row lengths, the largest it can be for these rows.
+    pub fn try_inverse(&self) -> Option<Self> {
+        let det = self.determinant();
+        let bound: f64 = self
+            .value
+            .iter()
+            .map(|row| row.iter().map(|v| v * v).sum::<f64>().sqrt())
+            .product();
+
+        if det.abs() <= bound * f64::EPSILON * 16.0 {
+            return None;
+        }
+
+        let mut value = [[0.0; 4]; 4];
+
+        // adjugate: transposed cofactor matrix
+        for (i, row) in value.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                let sign = if (i + j) % 2 == 0 { 1.0 } else { -1.0 };
+                *cell = sign * self.minor(j, i) / det;
+            }
+        }
+
+        Some(Self { value })
+    }
+
+    fn minor(&self, row: usize, col: usize) -> f64 {
+        let mut m = [[0.0; 3]; 3];
+        let rows = (0..4).filter(|&r| r != row);
+
+        for (mi, r) in rows.enumerate() {
+            let cols = (0..4).filter(|&c| c != col);
+
+            for (mj, c) in cols.enumerate() {
+                m[mi][mj] = self.value[r][c];
+            }
+        }
+
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+}
 
 impl AsRef<[[f64; 4]; 4]> for Mat4 {
     fn as_ref(&self) -> &[[f64; 4]; 4] {
@@ -45,3 +121,138 @@ impl Mul<Vec4> for Mat4 {
         )
     }
 }
+
+impl Mul<Mat4> for Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, rhs: Mat4) -> Self::Output {
+        let mut value = [[0.0; 4]; 4];
+
+        for (i, row) in value.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                *cell = (0..4).map(|k| self.value[i][k] * rhs.value[k][j]).sum();
+            }
+        }
+
+        Self { value }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::new_scaling;
+    use super::*;
+
+    fn sample() -> Mat4 {
+        Mat4::from([
+            [2.0, 0.0, 1.0, 3.0],
+            [1.0, 1.0, 0.0, 2.0],
+            [0.0, 4.0, 1.0, 0.0],
+            [1.0, 0.0, 2.0, 1.0],
+        ])
+    }
+
+    fn assert_approx_eq(a: Mat4, b: Mat4) {
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!(
+                    (a.value[i][j] - b.value[i][j]).abs() < 1e-9,
+                    "expected {b:?}, got {a:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_identity() {
+        let m = sample();
+
+        assert_eq!(Mat4::identity() * m, m);
+        assert_eq!(m * Mat4::identity(), m);
+    }
+
+    #[test]
+    fn test_mul() {
+        let a = Mat4::from([
+            [1.0, 2.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let b = Mat4::from([
+            [1.0, 0.0, 0.0, 0.0],
+            [3.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        assert_eq!(
+            a * b,
+            Mat4::from([
+                [7.0, 2.0, 0.0, 0.0],
+                [3.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ])
+        );
+    }
+
+    #[test]
+    fn test_mul_is_associative_with_vec() {
+        let a = sample();
+        let b = sample().transpose();
+        let v = Vec4::new(1.0, -2.0, 3.0, 1.0);
+
+        assert_eq!(((a * b) * v).value, (a * (b * v)).value);
+    }
+
+    #[test]
+    fn test_transpose() {
+        let m = sample();
+        let t = m.transpose();
+
+        assert_eq!(t.value[0], [2.0, 1.0, 0.0, 1.0]);
+        assert_eq!(t.transpose(), m);
+    }
+
+    #[test]
+    fn test_determinant() {
+        assert_eq!(Mat4::identity().determinant(), 1.0);
+        assert_eq!(sample().determinant(), 3.0);
+        assert_eq!(Mat4::default().determinant(), 0.0);
+    }
+
+    #[test]
+    fn test_try_inverse() {
+        let m = sample();
+        let inv = m.try_inverse().expect("expected invertible");
+
+        assert_approx_eq(m * inv, Mat4::identity());
+        assert_approx_eq(inv * m, Mat4::identity());
+    }
+
+    #[test]
+    fn test_try_inverse_singular() {
+        let m = Mat4::from([
+            [1.0, 2.0, 3.0, 4.0],
+            [2.0, 4.0, 6.0, 8.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        assert!(m.try_inverse().is_none());
+
+        let scaled = Mat4::from(m.value.map(|row| row.map(|v| v * 1e6)));
+
+        assert!(scaled.try_inverse().is_none());
+    }
+
+    #[test]
+    fn test_try_inverse_small_scale() {
+        let m = new_scaling(1e-6, 1e-6, 1e-6);
+        let inv = m.try_inverse().expect("expected invertible");
+
+        assert_approx_eq(m * inv, Mat4::identity());
+        assert!((inv.value[0][0] - 1e6).abs() < 1e-3);
+    }
+}
@@ -1,9 +1,12 @@
 pub use mat::Mat4;
+pub use quat::Quat;
 pub use transformation::{
-    new_perspective, new_rotation_x, new_rotation_y, new_rotation_z, new_translation,
+    look_at, new_orthographic, new_perspective, new_rotation_x, new_rotation_y, new_rotation_z,
+    new_scaling, new_translation,
 };
-pub use vec::Vec4;
+pub use vec::{Vec3, Vec4};
 
 mod mat;
+mod quat;
 mod transformation;
 mod vec;
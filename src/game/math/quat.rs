@@ -0,0 +1,243 @@
+use std::ops::Mul;
+
+use super::{Mat4, Vec3};
+
+/// Rotation quaternion stored as `[x, y, z, w]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quat {
+    pub value: [f64; 4],
+}
+
+impl Quat {
+    pub fn new(x: f64, y: f64, z: f64, w: f64) -> Self {
+        Self {
+            value: [x, y, z, w],
+        }
+    }
+
+    pub fn identity() -> Self {
+        Self::new(0.0, 0.0, 0.0, 1.0)
+    }
+
+    /// `axis` does not need to be normalized, `angle` is in radians.
+    pub fn from_axis_angle(axis: Vec3, angle: f64) -> Self {
+        let axis = axis.normalize();
+        let (sin, cos) = (angle * 0.5).sin_cos();
+
+        Self::new(axis.x() * sin, axis.y() * sin, axis.z() * sin, cos)
+    }
+
+    pub fn x(&self) -> f64 {
+        self.value[0]
+    }
+
+    pub fn y(&self) -> f64 {
+        self.value[1]
+    }
+
+    pub fn z(&self) -> f64 {
+        self.value[2]
+    }
+
+    pub fn w(&self) -> f64 {
+        self.value[3]
+    }
+
+    pub fn dot(&self, rhs: &Quat) -> f64 {
+        self.value
+            .iter()
+            .zip(rhs.value.iter())
+            .map(|(a, b)| a * b)
+            .sum()
+    }
+
+    pub fn length(&self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalize(&self) -> Quat {
+        let length = self.length();
+
+        Self::new(
+            self.x() / length,
+            self.y() / length,
+            self.z() / length,
+            self.w() / length,
+        )
+    }
+
+    pub fn conjugate(&self) -> Quat {
+        Self::new(-self.x(), -self.y(), -self.z(), self.w())
+    }
+
+    pub fn rotate(&self, v: Vec3) -> Vec3 {
+        let p = *self * Quat::new(v.x(), v.y(), v.z(), 0.0) * self.conjugate();
+
+        Vec3::new(p.x(), p.y(), p.z())
+    }
+
+    /// Spherical interpolation along the shortest arc between two unit quaternions.
+    pub fn slerp(&self, rhs: &Quat, t: f64) -> Quat {
+        let mut cos = self.dot(rhs);
+        let mut rhs = *rhs;
+
+        if cos < 0.0 {
+            cos = -cos;
+            rhs = Self::new(-rhs.x(), -rhs.y(), -rhs.z(), -rhs.w());
+        }
+
+        let (a, b) = if cos > 1.0 - 1e-6 {
+            // nearly parallel, fall back to a linear interpolation
+            (1.0 - t, t)
+        } else {
+            let theta = cos.acos();
+            let sin = theta.sin();
+
+            (((1.0 - t) * theta).sin() / sin, (t * theta).sin() / sin)
+        };
+
+        Self::new(
+            a * self.x() + b * rhs.x(),
+            a * self.y() + b * rhs.y(),
+            a * self.z() + b * rhs.z(),
+            a * self.w() + b * rhs.w(),
+        )
+        .normalize()
+    }
+}
+
+impl Default for Quat {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Mul<Quat> for Quat {
+    type Output = Quat;
+
+    /// Hamilton product, `a * b` applies `b` first then `a`.
+    fn mul(self, rhs: Quat) -> Self::Output {
+        let [x1, y1, z1, w1] = self.value;
+        let [x2, y2, z2, w2] = rhs.value;
+
+        Quat::new(
+            w1 * x2 + x1 * w2 + y1 * z2 - z1 * y2,
+            w1 * y2 - x1 * z2 + y1 * w2 + z1 * x2,
+            w1 * z2 + x1 * y2 - y1 * x2 + z1 * w2,
+            w1 * w2 - x1 * x2 - y1 * y2 - z1 * z2,
+        )
+    }
+}
+
+impl From<Quat> for Mat4 {
+    fn from(q: Quat) -> Self {
+        let [x, y, z, w] = q.normalize().value;
+
+        Mat4 {
+            value: [
+                [
+                    1.0 - 2.0 * (y * y + z * z),
+                    2.0 * (x * y - z * w),
+                    2.0 * (x * z + y * w),
+                    0.0,
+                ],
+                [
+                    2.0 * (x * y + z * w),
+                    1.0 - 2.0 * (x * x + z * z),
+                    2.0 * (y * z - x * w),
+                    0.0,
+                ],
+                [
+                    2.0 * (x * z - y * w),
+                    2.0 * (y * z + x * w),
+                    1.0 - 2.0 * (x * x + y * y),
+                    0.0,
+                ],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+
+    use super::super::{new_rotation_x, new_rotation_y, new_rotation_z, Vec4};
+    use super::*;
+
+    fn assert_vec_approx_eq(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-9, "expected {b:?}, got {a:?}");
+    }
+
+    fn assert_mat_approx_eq(a: Mat4, b: Mat4) {
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!(
+                    (a.value[i][j] - b.value[i][j]).abs() < 1e-9,
+                    "expected {b:?}, got {a:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_axis_angle() {
+        let q = Quat::from_axis_angle(Vec3::new(0.0, 0.0, 2.0), FRAC_PI_2);
+
+        assert!((q.length() - 1.0).abs() < 1e-9);
+        assert_vec_approx_eq(q.rotate(Vec3::new(1.0, 0.0, 0.0)), Vec3::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_to_mat4_matches_rotation_matrices() {
+        let angle = 0.3;
+
+        assert_mat_approx_eq(
+            Quat::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), angle).into(),
+            new_rotation_x(angle),
+        );
+        assert_mat_approx_eq(
+            Quat::from_axis_angle(Vec3::new(1.0, 0.0, 0.0), angle).into(),
+            new_rotation_y(angle),
+        );
+        assert_mat_approx_eq(
+            Quat::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), angle).into(),
+            new_rotation_z(angle),
+        );
+    }
+
+    #[test]
+    fn test_mul() {
+        let a = Quat::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), FRAC_PI_2);
+        let b = Quat::from_axis_angle(Vec3::new(1.0, 0.0, 0.0), FRAC_PI_2);
+        let v = Vec3::new(0.0, 1.0, 0.0);
+
+        assert_vec_approx_eq((a * b).rotate(v), a.rotate(b.rotate(v)));
+
+        let m = Mat4::from(a * b);
+        let r = Vec3::from(m * Vec4::from(v));
+
+        assert_vec_approx_eq(r, a.rotate(b.rotate(v)));
+    }
+
+    #[test]
+    fn test_normalize() {
+        let q = Quat::new(0.0, 0.0, 3.0, 4.0).normalize();
+
+        assert_eq!(q, Quat::new(0.0, 0.0, 0.6, 0.8));
+    }
+
+    #[test]
+    fn test_slerp() {
+        let axis = Vec3::new(0.0, 1.0, 0.0);
+        let a = Quat::identity();
+        let b = Quat::from_axis_angle(axis, FRAC_PI_2);
+        let half = a.slerp(&b, 0.5);
+        let expected = Quat::from_axis_angle(axis, FRAC_PI_4);
+
+        assert!((half.dot(&expected) - 1.0).abs() < 1e-9);
+        assert!((a.slerp(&b, 0.0).dot(&a) - 1.0).abs() < 1e-9);
+        assert!((a.slerp(&b, 1.0).dot(&b) - 1.0).abs() < 1e-9);
+    }
+}
@@ -1,4 +1,4 @@
-use super::Mat4;
+use super::{Mat4, Vec3};
 
 pub fn new_translation(x: f64, y: f64, z: f64) -> Mat4 {
     Mat4 {
@@ -11,6 +11,17 @@ pub fn new_translation(x: f64, y: f64, z: f64) -> Mat4 {
     }
 }
 
+pub fn new_scaling(x: f64, y: f64, z: f64) -> Mat4 {
+    Mat4 {
+        value: [
+            [x, 0.0, 0.0, 0.0],
+            [0.0, y, 0.0, 0.0],
+            [0.0, 0.0, z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ],
+    }
+}
+
 pub fn new_rotation_x(angle: f64) -> Mat4 {
     Mat4 {
         value: [
@@ -59,3 +70,95 @@ pub fn new_perspective(w: f64, h: f64, fov: f64, z_near: f64, z_far: f64) -> Mat
         ],
     }
 }
+
+/// Maps the box to `[-1, 1]` on `x` and `y` and to `[0, 1]` on `z`, like `new_perspective`.
+pub fn new_orthographic(
+    left: f64,
+    right: f64,
+    bottom: f64,
+    top: f64,
+    z_near: f64,
+    z_far: f64,
+) -> Mat4 {
+    Mat4 {
+        value: [
+            [
+                2.0 / (right - left),
+                0.0,
+                0.0,
+                -(right + left) / (right - left),
+            ],
+            [
+                0.0,
+                2.0 / (top - bottom),
+                0.0,
+                -(top + bottom) / (top - bottom),
+            ],
+            [0.0, 0.0, 1.0 / (z_far - z_near), -z_near / (z_far - z_near)],
+            [0.0, 0.0, 0.0, 1.0],
+        ],
+    }
+}
+
+/// View matrix for a camera at `eye` looking at `target`, the camera looks down `+z`.
+pub fn look_at(eye: Vec3, target: Vec3, up: Vec3) -> Mat4 {
+    let f = (target - eye).normalize();
+    let r = up.cross(&f).normalize();
+    let u = f.cross(&r);
+
+    Mat4 {
+        value: [
+            [r.x(), r.y(), r.z(), -r.dot(&eye)],
+            [u.x(), u.y(), u.z(), -u.dot(&eye)],
+            [f.x(), f.y(), f.z(), -f.dot(&eye)],
+            [0.0, 0.0, 0.0, 1.0],
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Vec4;
+    use super::*;
+
+    #[test]
+    fn test_orthographic() {
+        let m = new_orthographic(-4.0, 2.0, -1.0, 3.0, 2.0, 10.0);
+
+        assert_eq!(
+            m * Vec4::new(-4.0, -1.0, 2.0, 1.0),
+            Vec4::new(-1.0, -1.0, 0.0, 1.0)
+        );
+        assert_eq!(
+            m * Vec4::new(2.0, 3.0, 10.0, 1.0),
+            Vec4::new(1.0, 1.0, 1.0, 1.0)
+        );
+        assert_eq!(
+            m * Vec4::new(-1.0, 1.0, 6.0, 1.0),
+            Vec4::new(0.0, 0.0, 0.5, 1.0)
+        );
+    }
+
+    #[test]
+    fn test_look_at() {
+        let view = look_at(
+            Vec3::new(0.0, 0.0, -3.0),
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+
+        assert_eq!(view, new_translation(0.0, 0.0, 3.0));
+    }
+
+    #[test]
+    fn test_look_at_moves_target_in_front() {
+        let eye = Vec3::new(2.0, 1.0, -4.0);
+        let target = Vec3::new(-1.0, 0.5, 2.0);
+        let view = look_at(eye, target, Vec3::new(0.0, 1.0, 0.0));
+        let r = view * Vec4::from(target);
+
+        assert!(r.x().abs() < 1e-9);
+        assert!(r.y().abs() < 1e-9);
+        assert!((r.z() - (target - eye).length()).abs() < 1e-9);
+    }
+}
@@ -1,8 +1,8 @@
-use std::ops::Mul;
+use std::ops::{Add, Div, Mul, Neg, Sub};
 
 use super::Mat4;
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub struct Vec4 {
     pub value: [f64; 4],
 }
@@ -70,18 +70,311 @@ impl Mul<Mat4> for Vec4 {
     }
 }
 
-impl From<(i32, i32, i32)> for Vec4 {
-    fn from((x, y, z): (i32, i32, i32)) -> Self {
-        Self {
-            value: [x as f64, y as f64, z as f64, 1.0],
-        }
+impl Vec4 {
+    pub fn dot(&self, rhs: &Vec4) -> f64 {
+        self.value
+            .iter()
+            .zip(rhs.value.iter())
+            .map(|(a, b)| a * b)
+            .sum()
+    }
+
+    /// Cross product of the `xyz` components, the result is a direction (`w = 0`).
+    pub fn cross(&self, rhs: &Vec4) -> Vec4 {
+        let v =
+            Vec3::new(self.x(), self.y(), self.z()).cross(&Vec3::new(rhs.x(), rhs.y(), rhs.z()));
+
+        Vec4::new(v.x(), v.y(), v.z(), 0.0)
+    }
+
+    pub fn length(&self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalize(&self) -> Vec4 {
+        *self / self.length()
+    }
+
+    pub fn lerp(&self, rhs: &Vec4, t: f64) -> Vec4 {
+        *self + (*rhs - *self) * t
     }
 }
 
-impl From<&(i32, i32, i32)> for Vec4 {
-    fn from((x, y, z): &(i32, i32, i32)) -> Self {
-        Self {
-            value: [*x as f64, *y as f64, *z as f64, 1.0],
-        }
+impl Add for Vec4 {
+    type Output = Vec4;
+
+    fn add(self, rhs: Vec4) -> Self::Output {
+        Vec4::new(
+            self.value[0] + rhs.value[0],
+            self.value[1] + rhs.value[1],
+            self.value[2] + rhs.value[2],
+            self.value[3] + rhs.value[3],
+        )
+    }
+}
+
+impl Sub for Vec4 {
+    type Output = Vec4;
+
+    fn sub(self, rhs: Vec4) -> Self::Output {
+        Vec4::new(
+            self.value[0] - rhs.value[0],
+            self.value[1] - rhs.value[1],
+            self.value[2] - rhs.value[2],
+            self.value[3] - rhs.value[3],
+        )
+    }
+}
+
+impl Neg for Vec4 {
+    type Output = Vec4;
+
+    fn neg(self) -> Self::Output {
+        Vec4::new(
+            -self.value[0],
+            -self.value[1],
+            -self.value[2],
+            -self.value[3],
+        )
+    }
+}
+
+impl Mul<f64> for Vec4 {
+    type Output = Vec4;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Vec4::new(
+            self.value[0] * rhs,
+            self.value[1] * rhs,
+            self.value[2] * rhs,
+            self.value[3] * rhs,
+        )
+    }
+}
+
+impl Div<f64> for Vec4 {
+    type Output = Vec4;
+
+    fn div(self, rhs: f64) -> Self::Output {
+        Vec4::new(
+            self.value[0] / rhs,
+            self.value[1] / rhs,
+            self.value[2] / rhs,
+            self.value[3] / rhs,
+        )
+    }
+}
+
+impl From<Vec3> for Vec4 {
+    fn from(v: Vec3) -> Self {
+        Vec4::new(v.x(), v.y(), v.z(), 1.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Vec3 {
+    pub value: [f64; 3],
+}
+
+impl Vec3 {
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Self { value: [x, y, z] }
+    }
+
+    pub fn x(&self) -> f64 {
+        self.value[0]
+    }
+
+    pub fn y(&self) -> f64 {
+        self.value[1]
+    }
+
+    pub fn z(&self) -> f64 {
+        self.value[2]
+    }
+
+    pub fn dot(&self, rhs: &Vec3) -> f64 {
+        self.value[0] * rhs.value[0] + self.value[1] * rhs.value[1] + self.value[2] * rhs.value[2]
+    }
+
+    pub fn cross(&self, rhs: &Vec3) -> Vec3 {
+        Vec3::new(
+            self.value[1] * rhs.value[2] - self.value[2] * rhs.value[1],
+            self.value[2] * rhs.value[0] - self.value[0] * rhs.value[2],
+            self.value[0] * rhs.value[1] - self.value[1] * rhs.value[0],
+        )
+    }
+
+    pub fn length(&self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalize(&self) -> Vec3 {
+        *self / self.length()
+    }
+
+    pub fn lerp(&self, rhs: &Vec3, t: f64) -> Vec3 {
+        *self + (*rhs - *self) * t
+    }
+}
+
+impl AsRef<[f64; 3]> for Vec3 {
+    fn as_ref(&self) -> &[f64; 3] {
+        &self.value
+    }
+}
+
+impl From<[f64; 3]> for Vec3 {
+    fn from(value: [f64; 3]) -> Self {
+        Self { value }
+    }
+}
+
+/// Homogeneous divide, `w` is expected to be non-zero.
+impl From<Vec4> for Vec3 {
+    fn from(v: Vec4) -> Self {
+        Vec3::new(v.x() / v.w(), v.y() / v.w(), v.z() / v.w())
+    }
+}
+
+impl Add for Vec3 {
+    type Output = Vec3;
+
+    fn add(self, rhs: Vec3) -> Self::Output {
+        Vec3::new(
+            self.value[0] + rhs.value[0],
+            self.value[1] + rhs.value[1],
+            self.value[2] + rhs.value[2],
+        )
+    }
+}
+
+impl Sub for Vec3 {
+    type Output = Vec3;
+
+    fn sub(self, rhs: Vec3) -> Self::Output {
+        Vec3::new(
+            self.value[0] - rhs.value[0],
+            self.value[1] - rhs.value[1],
+            self.value[2] - rhs.value[2],
+        )
+    }
+}
+
+impl Neg for Vec3 {
+    type Output = Vec3;
+
+    fn neg(self) -> Self::Output {
+        Vec3::new(-self.value[0], -self.value[1], -self.value[2])
+    }
+}
+
+impl Mul<f64> for Vec3 {
+    type Output = Vec3;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Vec3::new(
+            self.value[0] * rhs,
+            self.value[1] * rhs,
+            self.value[2] * rhs,
+        )
+    }
+}
+
+impl Div<f64> for Vec3 {
+    type Output = Vec3;
+
+    fn div(self, rhs: f64) -> Self::Output {
+        Vec3::new(
+            self.value[0] / rhs,
+            self.value[1] / rhs,
+            self.value[2] / rhs,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_approx_eq(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "expected {b}, got {a}");
+    }
+
+    #[test]
+    fn test_vec3_ops() {
+        let a = Vec3::new(1.0, 2.0, 3.0);
+        let b = Vec3::new(4.0, 5.0, 6.0);
+
+        assert_eq!(a + b, Vec3::new(5.0, 7.0, 9.0));
+        assert_eq!(b - a, Vec3::new(3.0, 3.0, 3.0));
+        assert_eq!(-a, Vec3::new(-1.0, -2.0, -3.0));
+        assert_eq!(a * 2.0, Vec3::new(2.0, 4.0, 6.0));
+        assert_eq!(b / 2.0, Vec3::new(2.0, 2.5, 3.0));
+    }
+
+    #[test]
+    fn test_vec3_dot_cross() {
+        let x = Vec3::new(1.0, 0.0, 0.0);
+        let y = Vec3::new(0.0, 1.0, 0.0);
+
+        assert_eq!(x.dot(&y), 0.0);
+        assert_eq!(
+            Vec3::new(1.0, 2.0, 3.0).dot(&Vec3::new(4.0, 5.0, 6.0)),
+            32.0
+        );
+        assert_eq!(x.cross(&y), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(y.cross(&x), Vec3::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn test_vec3_length_normalize() {
+        let v = Vec3::new(3.0, 0.0, 4.0);
+
+        assert_eq!(v.length(), 5.0);
+        assert_approx_eq(v.normalize().length(), 1.0);
+        assert_eq!(v.normalize(), Vec3::new(0.6, 0.0, 0.8));
+    }
+
+    #[test]
+    fn test_vec3_lerp() {
+        let a = Vec3::new(0.0, 0.0, 0.0);
+        let b = Vec3::new(2.0, 4.0, -2.0);
+
+        assert_eq!(a.lerp(&b, 0.0), a);
+        assert_eq!(a.lerp(&b, 1.0), b);
+        assert_eq!(a.lerp(&b, 0.5), Vec3::new(1.0, 2.0, -1.0));
+    }
+
+    #[test]
+    fn test_vec4_ops() {
+        let a = Vec4::new(1.0, 2.0, 3.0, 4.0);
+        let b = Vec4::new(4.0, 3.0, 2.0, 1.0);
+
+        assert_eq!(a + b, Vec4::new(5.0, 5.0, 5.0, 5.0));
+        assert_eq!(a - b, Vec4::new(-3.0, -1.0, 1.0, 3.0));
+        assert_eq!(-a, Vec4::new(-1.0, -2.0, -3.0, -4.0));
+        assert_eq!(a * 2.0, Vec4::new(2.0, 4.0, 6.0, 8.0));
+        assert_eq!(a / 2.0, Vec4::new(0.5, 1.0, 1.5, 2.0));
+        assert_eq!(a.dot(&b), 20.0);
+        assert_eq!(a.lerp(&b, 0.5), Vec4::new(2.5, 2.5, 2.5, 2.5));
+        assert_approx_eq(a.normalize().length(), 1.0);
+    }
+
+    #[test]
+    fn test_vec4_cross() {
+        let x = Vec4::new(1.0, 0.0, 0.0, 0.0);
+        let y = Vec4::new(0.0, 1.0, 0.0, 0.0);
+
+        assert_eq!(x.cross(&y), Vec4::new(0.0, 0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_conversions() {
+        let v = Vec3::new(1.0, 2.0, 3.0);
+
+        assert_eq!(Vec4::from(v), Vec4::new(1.0, 2.0, 3.0, 1.0));
+        assert_eq!(Vec3::from(Vec4::new(2.0, 4.0, 6.0, 2.0)), v);
+        assert_eq!(Vec3::from(Vec4::from(v)), v);
     }
 }
@@ -0,0 +1,328 @@
+use std::{
+    collections::BTreeSet,
+    f64::consts::{PI, TAU},
+};
+
+use super::{clip::BoundingSphere, math::Vec3};
+
+/// Indexed triangle mesh. `normals` and `uvs` are either empty or have one entry per vertex.
+/// Triangles are counter-clockwise when seen from the side their normal points to.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mesh {
+    pub vertices: Vec<Vec3>,
+    pub triangles: Vec<[usize; 3]>,
+    /// Wireframe edges, derived from `triangles` when `None`.
+    pub edges: Option<Vec<[usize; 2]>>,
+    pub normals: Vec<Vec3>,
+    pub uvs: Vec<[f64; 2]>,
+}
+
+impl Mesh {
+    /// Axis aligned cube centered on the origin with flat normals, 4 vertices per face.
+    pub fn cube(size: f64) -> Self {
+        let h = size * 0.5;
+        let faces = [
+            (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0)),
+            (Vec3::new(-1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0)),
+            (Vec3::new(0.0, 1.0, 0.0), Vec3::new(1.0, 0.0, 0.0)),
+            (Vec3::new(0.0, -1.0, 0.0), Vec3::new(-1.0, 0.0, 0.0)),
+            (Vec3::new(0.0, 0.0, 1.0), Vec3::new(-1.0, 0.0, 0.0)),
+            (Vec3::new(0.0, 0.0, -1.0), Vec3::new(1.0, 0.0, 0.0)),
+        ];
+
+        let mut mesh = Mesh::default();
+        let mut edges = BTreeSet::new();
+
+        for (normal, u) in faces {
+            let v = normal.cross(&u);
+            let base = mesh.vertices.len();
+
+            for (s, t) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+                mesh.vertices.push((normal + u * s + v * t) * h);
+                mesh.normals.push(normal);
+                mesh.uvs.push([(s + 1.0) * 0.5, (t + 1.0) * 0.5]);
+            }
+
+            mesh.triangles.push([base, base + 1, base + 2]);
+            mesh.triangles.push([base, base + 2, base + 3]);
+
+            // wireframe edges reference the first vertex found at each corner
+            for i in 0..4 {
+                let a = corner_vertex(&mesh.vertices, mesh.vertices[base + i]);
+                let b = corner_vertex(&mesh.vertices, mesh.vertices[base + (i + 1) % 4]);
+
+                edges.insert([a.min(b), a.max(b)]);
+            }
+        }
+
+        mesh.edges = Some(edges.into_iter().collect());
+        mesh
+    }
+
+    /// Horizontal plane on `y = 0` facing `+y`, split in `subdivisions` quads on each side.
+    pub fn plane(size: f64, subdivisions: usize) -> Self {
+        let subdivisions = subdivisions.max(1);
+
+        grid(subdivisions, subdivisions, false, |u, v| {
+            (
+                Vec3::new((u - 0.5) * size, 0.0, (0.5 - v) * size),
+                Vec3::new(0.0, 1.0, 0.0),
+            )
+        })
+    }
+
+    pub fn sphere(radius: f64, segments: usize, rings: usize) -> Self {
+        grid(segments.max(3), rings.max(2), true, |u, v| {
+            let theta = u * TAU;
+            let phi = v * PI;
+            let normal = Vec3::new(phi.sin() * theta.cos(), phi.cos(), phi.sin() * theta.sin());
+
+            (normal * radius, normal)
+        })
+    }
+
+    /// Closed cylinder along `y` centered on the origin.
+    pub fn cylinder(radius: f64, height: f64, segments: usize) -> Self {
+        let segments = segments.max(3);
+        let mut mesh = grid(segments, 1, true, |u, v| {
+            let theta = u * TAU;
+            let normal = Vec3::new(theta.cos(), 0.0, theta.sin());
+
+            (
+                normal * radius + Vec3::new(0.0, (0.5 - v) * height, 0.0),
+                normal,
+            )
+        });
+
+        for (y, normal) in [(0.5, 1.0), (-0.5, -1.0)] {
+            let center = mesh.vertices.len();
+
+            mesh.vertices.push(Vec3::new(0.0, y * height, 0.0));
+            mesh.normals.push(Vec3::new(0.0, normal, 0.0));
+            mesh.uvs.push([0.5, 0.5]);
+
+            for i in 0..segments {
+                let theta = i as f64 / segments as f64 * TAU;
+
+                mesh.vertices.push(Vec3::new(
+                    theta.cos() * radius,
+                    y * height,
+                    theta.sin() * radius,
+                ));
+                mesh.normals.push(Vec3::new(0.0, normal, 0.0));
+                mesh.uvs
+                    .push([0.5 + theta.cos() * 0.5, 0.5 + theta.sin() * 0.5]);
+            }
+
+            for i in 0..segments {
+                let a = center + 1 + i;
+                let b = center + 1 + (i + 1) % segments;
+
+                mesh.triangles.push(if normal > 0.0 {
+                    [center, b, a]
+                } else {
+                    [center, a, b]
+                });
+
+                // the grid only outlines the side, caps get their rim and spokes
+                if let Some(edges) = &mut mesh.edges {
+                    edges.push([a, b]);
+                    edges.push([center, a]);
+                }
+            }
+        }
+
+        mesh
+    }
+
+    /// Torus lying on the `xz` plane, `major` is the distance from the center to the tube.
+    pub fn torus(major: f64, minor: f64, major_segments: usize, minor_segments: usize) -> Self {
+        grid(
+            major_segments.max(3),
+            minor_segments.max(3),
+            true,
+            |u, v| {
+                let theta = u * TAU;
+                let phi = -v * TAU;
+                let ring = Vec3::new(theta.cos(), 0.0, theta.sin());
+                let normal = ring * phi.cos() + Vec3::new(0.0, phi.sin(), 0.0);
+
+                (ring * major + normal * minor, normal)
+            },
+        )
+    }
+
+    /// Explicit edges, or every unique triangle edge when there are none.
+    pub fn edges(&self) -> Vec<[usize; 2]> {
+        if let Some(edges) = &self.edges {
+            return edges.clone();
+        }
+
+        let mut edges = BTreeSet::new();
+
+        for [a, b, c] in &self.triangles {
+            for (a, b) in [(a, b), (b, c), (c, a)] {
+                edges.insert([*a.min(b), *a.max(b)]);
+            }
+        }
+
+        edges.into_iter().collect()
+    }
+
+    /// Sphere enclosing every vertex, in model space.
+    pub fn bounding_sphere(&self) -> BoundingSphere {
+        BoundingSphere::from_points(&self.vertices)
+    }
+}
+
+fn corner_vertex(vertices: &[Vec3], position: Vec3) -> usize {
+    vertices.iter().position(|v| *v == position).unwrap()
+}
+
+/// Builds a `columns` x `rows` grid of quads from a `(u, v) -> (position, normal)` function.
+/// When `wrap` is set the last column overlaps the first one, it keeps its own vertices so that UVs
+/// stay continuous but does not get its own wireframe edges.
+fn grid(columns: usize, rows: usize, wrap: bool, f: impl Fn(f64, f64) -> (Vec3, Vec3)) -> Mesh {
+    let mut mesh = Mesh::default();
+    let mut edges = vec![];
+    let stride = columns + 1;
+
+    for row in 0..=rows {
+        for column in 0..=columns {
+            let u = column as f64 / columns as f64;
+            let v = row as f64 / rows as f64;
+            let (position, normal) = f(u, v);
+
+            mesh.vertices.push(position);
+            mesh.normals.push(normal);
+            mesh.uvs.push([u, v]);
+        }
+    }
+
+    for row in 0..=rows {
+        for column in 0..=columns {
+            let i = row * stride + column;
+
+            if column < columns {
+                edges.push([i, i + 1]);
+            }
+
+            if row < rows && !(wrap && column == columns) {
+                edges.push([i, i + stride]);
+            }
+
+            if row < rows && column < columns {
+                mesh.triangles.push([i, i + 1, i + stride + 1]);
+                mesh.triangles.push([i, i + stride + 1, i + stride]);
+            }
+        }
+    }
+
+    mesh.edges = Some(edges);
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_valid(mesh: &Mesh) {
+        let n = mesh.vertices.len();
+
+        assert_eq!(mesh.normals.len(), n);
+        assert_eq!(mesh.uvs.len(), n);
+        assert!(mesh.triangles.iter().flatten().all(|&i| i < n));
+        assert!(mesh.edges().iter().flatten().all(|&i| i < n));
+        assert!(mesh
+            .normals
+            .iter()
+            .all(|normal| (normal.length() - 1.0).abs() < 1e-9));
+
+        for [a, b, c] in &mesh.triangles {
+            let (pa, pb, pc) = (mesh.vertices[*a], mesh.vertices[*b], mesh.vertices[*c]);
+            let face_normal = (pb - pa).cross(&(pc - pa));
+            let normal = mesh.normals[*a] + mesh.normals[*b] + mesh.normals[*c];
+
+            if face_normal.length() > 1e-9 {
+                assert!(face_normal.dot(&normal) > 0.0, "wrong winding");
+            }
+        }
+    }
+
+    #[test]
+    fn test_cube() {
+        let mesh = Mesh::cube(2.0);
+
+        assert_valid(&mesh);
+        assert_eq!(mesh.vertices.len(), 24);
+        assert_eq!(mesh.triangles.len(), 12);
+        assert_eq!(mesh.edges().len(), 12);
+        assert!(mesh
+            .vertices
+            .iter()
+            .all(|v| v.x().abs() == 1.0 && v.y().abs() == 1.0 && v.z().abs() == 1.0));
+    }
+
+    #[test]
+    fn test_plane() {
+        let mesh = Mesh::plane(2.0, 2);
+
+        assert_valid(&mesh);
+        assert_eq!(mesh.vertices.len(), 9);
+        assert_eq!(mesh.triangles.len(), 8);
+        assert_eq!(mesh.edges().len(), 12);
+    }
+
+    #[test]
+    fn test_sphere() {
+        let mesh = Mesh::sphere(2.0, 8, 4);
+
+        assert_valid(&mesh);
+        assert!(mesh
+            .vertices
+            .iter()
+            .all(|v| (v.length() - 2.0).abs() < 1e-9));
+    }
+
+    #[test]
+    fn test_cylinder() {
+        let mesh = Mesh::cylinder(1.0, 2.0, 8);
+
+        assert_valid(&mesh);
+        assert_eq!(mesh.triangles.len(), 8 * 2 + 8 * 2);
+        assert!(mesh.vertices.iter().all(|v| v.y().abs() == 1.0));
+
+        // side rims and seams, then the rim and spokes of each cap
+        let edges = mesh.edges();
+
+        assert_eq!(edges.len(), 8 * 3 + 2 * 8 * 2);
+
+        for center in [mesh.vertices.len() - 18, mesh.vertices.len() - 9] {
+            assert_eq!(mesh.vertices[center].x(), 0.0);
+            assert_eq!(edges.iter().filter(|edge| edge[0] == center).count(), 8);
+        }
+    }
+
+    #[test]
+    fn test_torus() {
+        let mesh = Mesh::torus(2.0, 0.5, 12, 6);
+
+        assert_valid(&mesh);
+        assert!(mesh.vertices.iter().all(|v| {
+            let ring = Vec3::new(v.x(), 0.0, v.z()).normalize() * 2.0;
+
+            ((*v - ring).length() - 0.5).abs() < 1e-9
+        }));
+    }
+
+    #[test]
+    fn test_derived_edges() {
+        let mesh = Mesh {
+            vertices: vec![Vec3::default(); 4],
+            triangles: vec![[0, 1, 2], [0, 2, 3]],
+            ..Default::default()
+        };
+
+        assert_eq!(mesh.edges(), vec![[0, 1], [0, 2], [0, 3], [1, 2], [2, 3]]);
+    }
+}
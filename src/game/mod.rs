@@ -1,11 +1,28 @@
 pub use declaration::GameOptions;
 pub use game::Game;
 
-mod assets_loader;
+pub mod animation;
+pub mod assets;
+pub mod assets_loader;
+pub mod backend;
+pub mod camera;
+pub mod clip;
 mod declaration;
-mod error;
+pub mod ecs;
+pub mod error;
+#[allow(clippy::module_inception)]
 mod game;
+pub mod gamepad;
 mod handler;
+pub mod input;
+pub mod light;
 mod logic;
-mod renderer;
-mod math;
\ No newline at end of file
+pub mod math;
+pub mod mesh;
+pub mod obj;
+pub mod raster;
+pub mod renderer;
+pub mod scene;
+pub mod sprite;
+pub mod timestep;
+pub mod touch;
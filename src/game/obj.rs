@@ -0,0 +1,432 @@
+use std::{collections::HashMap, ops::Range, str::FromStr};
+
+use super::{error::ObjError, math::Vec3, mesh::Mesh};
+
+/// Mesh parsed from a Wavefront OBJ file, all groups share the same vertex buffers.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ObjModel {
+    pub mesh: Mesh,
+    pub groups: Vec<ObjGroup>,
+    pub material_libs: Vec<String>,
+    pub materials: HashMap<String, Material>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ObjGroup {
+    pub name: String,
+    pub material: Option<String>,
+    /// Range in `mesh.triangles`.
+    pub triangles: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Material {
+    pub name: String,
+    pub ambient: [f64; 3],
+    pub diffuse: [f64; 3],
+    pub specular: [f64; 3],
+    pub shininess: f64,
+    pub dissolve: f64,
+    pub diffuse_map: Option<String>,
+}
+
+impl Material {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ambient: [0.0; 3],
+            diffuse: [1.0; 3],
+            specular: [0.0; 3],
+            shininess: 0.0,
+            dissolve: 1.0,
+            diffuse_map: None,
+        }
+    }
+}
+
+impl FromStr for ObjModel {
+    type Err = ObjError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_obj(s)
+    }
+}
+
+pub fn parse_obj(source: &str) -> Result<ObjModel, ObjError> {
+    let mut positions = vec![];
+    let mut normals = vec![];
+    let mut uvs = vec![];
+    let mut model = ObjModel::default();
+    let mut vertices: HashMap<(usize, Option<usize>, Option<usize>), usize> = HashMap::new();
+    let mut group = ObjGroup {
+        name: "default".to_string(),
+        ..Default::default()
+    };
+
+    for (line, keyword, args) in lines(source) {
+        match keyword {
+            "v" => positions.push(parse_vec3(line, &args)?),
+            "vn" => normals.push(parse_vec3(line, &args)?.normalize()),
+            // `v` defaults to 0 and the depth `w` is not used
+            "vt" => {
+                let [u] = parse_floats::<1>(line, &args)?;
+                let v = if args.len() > 1 {
+                    parse_floats::<2>(line, &args)?[1]
+                } else {
+                    0.0
+                };
+
+                uvs.push([u, v]);
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return Err(ObjError::DegenerateFace(line));
+                }
+
+                let mut face = Vec::with_capacity(args.len());
+
+                for arg in &args {
+                    let key = parse_face_vertex(line, arg, &positions, &uvs, &normals)?;
+                    let index = *vertices.entry(key).or_insert_with(|| {
+                        model.mesh.vertices.push(positions[key.0]);
+                        model
+                            .mesh
+                            .uvs
+                            .push(key.1.map(|i| uvs[i]).unwrap_or_default());
+                        model
+                            .mesh
+                            .normals
+                            .push(key.2.map(|i| normals[i]).unwrap_or_default());
+                        model.mesh.vertices.len() - 1
+                    });
+
+                    face.push(index);
+                }
+
+                // polygons are triangulated as a fan around their first vertex
+                for i in 1..face.len() - 1 {
+                    let triangle = [face[0], face[i], face[i + 1]];
+
+                    fill_normals(&mut model.mesh, triangle);
+                    model.mesh.triangles.push(triangle);
+                }
+            }
+            "g" | "o" => {
+                let name = args.join(" ");
+                let material = group.material.clone();
+
+                push_group(&mut model, &mut group);
+                group.name = name;
+                group.material = material;
+            }
+            "usemtl" => {
+                let material = args.first().ok_or(ObjError::MissingValues(line, 1))?;
+
+                push_group(&mut model, &mut group);
+                group.material = Some(material.to_string());
+            }
+            "mtllib" => model
+                .material_libs
+                .extend(args.iter().map(|s| s.to_string())),
+            _ => log::debug!("line {line}: ignoring `{keyword}`"),
+        }
+    }
+
+    push_group(&mut model, &mut group);
+
+    if uvs.is_empty() {
+        model.mesh.uvs.clear();
+    }
+
+    Ok(model)
+}
+
+pub fn parse_mtl(source: &str) -> Result<HashMap<String, Material>, ObjError> {
+    let mut materials = HashMap::new();
+    let mut current: Option<Material> = None;
+
+    for (line, keyword, args) in lines(source) {
+        if keyword == "newmtl" {
+            let name = args.first().ok_or(ObjError::MissingValues(line, 1))?;
+
+            if let Some(material) = current.replace(Material::new(name)) {
+                materials.insert(material.name.clone(), material);
+            }
+
+            continue;
+        }
+
+        let material = current.as_mut().ok_or(ObjError::NoMaterial(line))?;
+
+        match keyword {
+            "Ka" => material.ambient = parse_floats::<3>(line, &args)?,
+            "Kd" => material.diffuse = parse_floats::<3>(line, &args)?,
+            "Ks" => material.specular = parse_floats::<3>(line, &args)?,
+            "Ns" => material.shininess = parse_floats::<1>(line, &args)?[0],
+            "d" => material.dissolve = parse_floats::<1>(line, &args)?[0],
+            "Tr" => material.dissolve = 1.0 - parse_floats::<1>(line, &args)?[0],
+            "map_Kd" => {
+                // options come before the file name, which is always last
+                let path = args.last().ok_or(ObjError::MissingValues(line, 1))?;
+                material.diffuse_map = Some(path.to_string());
+            }
+            _ => log::debug!("line {line}: ignoring `{keyword}`"),
+        }
+    }
+
+    if let Some(material) = current {
+        materials.insert(material.name.clone(), material);
+    }
+
+    Ok(materials)
+}
+
+/// Non empty lines without comments as `(line number, keyword, arguments)`.
+fn lines(source: &str) -> impl Iterator<Item = (usize, &str, Vec<&str>)> {
+    source.lines().enumerate().filter_map(|(i, line)| {
+        let line_number = i + 1;
+        let line = line.split('#').next().unwrap_or_default();
+        let mut tokens = line.split_whitespace();
+        let keyword = tokens.next()?;
+
+        Some((line_number, keyword, tokens.collect()))
+    })
+}
+
+fn push_group(model: &mut ObjModel, group: &mut ObjGroup) {
+    let end = model.mesh.triangles.len();
+
+    group.triangles.end = end;
+
+    if !group.triangles.is_empty() {
+        model.groups.push(group.clone());
+    }
+
+    group.triangles = end..end;
+}
+
+/// Uses the face normal for vertices that have none.
+fn fill_normals(mesh: &mut Mesh, [a, b, c]: [usize; 3]) {
+    let (pa, pb, pc) = (mesh.vertices[a], mesh.vertices[b], mesh.vertices[c]);
+    let normal = (pb - pa).cross(&(pc - pa));
+
+    if normal.length() == 0.0 {
+        return;
+    }
+
+    for i in [a, b, c] {
+        if mesh.normals[i] == Vec3::default() {
+            mesh.normals[i] = normal.normalize();
+        }
+    }
+}
+
+fn parse_floats<const N: usize>(line: usize, args: &[&str]) -> Result<[f64; N], ObjError> {
+    if args.len() < N {
+        return Err(ObjError::MissingValues(line, N));
+    }
+
+    let mut values = [0.0; N];
+
+    for (value, arg) in values.iter_mut().zip(args) {
+        *value = arg
+            .parse()
+            .map_err(|_| ObjError::InvalidNumber(line, arg.to_string()))?;
+    }
+
+    Ok(values)
+}
+
+fn parse_vec3(line: usize, args: &[&str]) -> Result<Vec3, ObjError> {
+    Ok(parse_floats::<3>(line, args)?.into())
+}
+
+/// Parses `v`, `v/vt`, `v//vn` or `v/vt/vn` into zero based indices.
+fn parse_face_vertex(
+    line: usize,
+    arg: &str,
+    positions: &[Vec3],
+    uvs: &[[f64; 2]],
+    normals: &[Vec3],
+) -> Result<(usize, Option<usize>, Option<usize>), ObjError> {
+    let mut parts = arg.split('/');
+    let position = parts.next().unwrap_or_default();
+    let uv = parts.next().filter(|s| !s.is_empty());
+    let normal = parts.next().filter(|s| !s.is_empty());
+
+    Ok((
+        resolve_index(line, position, positions.len())?,
+        uv.map(|uv| resolve_index(line, uv, uvs.len()))
+            .transpose()?,
+        normal
+            .map(|normal| resolve_index(line, normal, normals.len()))
+            .transpose()?,
+    ))
+}
+
+/// OBJ indices are one based, negative indices are relative to the end of the list.
+fn resolve_index(line: usize, index: &str, len: usize) -> Result<usize, ObjError> {
+    let i: i64 = index
+        .parse()
+        .map_err(|_| ObjError::InvalidNumber(line, index.to_string()))?;
+
+    let resolved = if i < 0 { len as i64 + i } else { i - 1 };
+
+    if i == 0 || resolved < 0 || resolved >= len as i64 {
+        return Err(ObjError::IndexOutOfRange(line, i));
+    }
+
+    Ok(resolved as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CUBE: &str = "
+# cube with one quad per face
+mtllib cube.mtl
+o Cube
+v -1 -1 -1
+v 1 -1 -1
+v 1 1 -1
+v -1 1 -1
+v -1 -1 1
+v 1 -1 1
+v 1 1 1
+v -1 1 1
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 -1
+vn 0 0 1
+usemtl red
+f 1/1/1 4/4/1 3/3/1 2/2/1
+f 5/1/2 6/2/2 7/3/2 8/4/2
+usemtl blue
+f 1 2 6 5
+f 4 8 7 3
+g sides
+f -8 -4 -1 -5
+f -7 -6 -2 -3
+";
+
+    const MTL: &str = "
+newmtl red
+Kd 1.0 0.0 0.0
+Ns 10
+newmtl blue
+Kd 0 0 1
+d 0.5
+map_Kd -s 1 1 1 textures/blue.png
+";
+
+    #[test]
+    fn test_parse_obj() {
+        let model = parse_obj(CUBE).unwrap();
+
+        assert_eq!(model.material_libs, vec!["cube.mtl"]);
+        assert_eq!(model.mesh.triangles.len(), 12);
+        assert_eq!(model.mesh.normals.len(), model.mesh.vertices.len());
+        assert_eq!(model.mesh.uvs.len(), model.mesh.vertices.len());
+        assert!(model
+            .mesh
+            .normals
+            .iter()
+            .all(|normal| (normal.length() - 1.0).abs() < 1e-9));
+    }
+
+    #[test]
+    fn test_groups() {
+        let model = parse_obj(CUBE).unwrap();
+        let groups: Vec<_> = model
+            .groups
+            .iter()
+            .map(|g| (g.name.as_str(), g.material.as_deref(), g.triangles.clone()))
+            .collect();
+
+        assert_eq!(
+            groups,
+            vec![
+                ("Cube", Some("red"), 0..4),
+                ("Cube", Some("blue"), 4..8),
+                ("sides", Some("blue"), 8..12),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shared_vertices() {
+        let model = parse_obj("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3\nf 1 3 4\n").unwrap();
+
+        assert_eq!(model.mesh.vertices.len(), 4);
+        assert_eq!(model.mesh.triangles, vec![[0, 1, 2], [0, 2, 3]]);
+        assert!(model.mesh.uvs.is_empty());
+        assert_eq!(model.mesh.normals[0], Vec3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_polygon_triangulation() {
+        let model =
+            parse_obj("v 0 0 0\nv 1 0 0\nv 2 1 0\nv 1 2 0\nv 0 1 0\nf 1 2 3 4 5\n").unwrap();
+
+        assert_eq!(model.mesh.triangles, vec![[0, 1, 2], [0, 2, 3], [0, 3, 4]]);
+    }
+
+    #[test]
+    fn test_uv_values() {
+        let model =
+            parse_obj("v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0.5\nvt 1 0.25 0\nvt 0 1\nf 1/1 2/2 3/3\n")
+                .unwrap();
+
+        assert_eq!(model.mesh.uvs, vec![[0.5, 0.0], [1.0, 0.25], [0.0, 1.0]]);
+        assert_eq!(parse_obj("vt\n"), Err(ObjError::MissingValues(1, 1)));
+        assert_eq!(
+            parse_obj("vt 0 x\n"),
+            Err(ObjError::InvalidNumber(1, "x".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(parse_obj("v 0 0\n"), Err(ObjError::MissingValues(1, 3)));
+        assert_eq!(
+            parse_obj("v 0 0 x\n"),
+            Err(ObjError::InvalidNumber(1, "x".to_string()))
+        );
+        assert_eq!(
+            parse_obj("v 0 0 0\nv 1 0 0\nf 1 2\n"),
+            Err(ObjError::DegenerateFace(3))
+        );
+        assert_eq!(
+            parse_obj("v 0 0 0\nv 1 0 0\nf 1 2 3\n"),
+            Err(ObjError::IndexOutOfRange(3, 3))
+        );
+        assert_eq!(
+            parse_obj("v 0 0 0\nf 1 0 -2\n"),
+            Err(ObjError::IndexOutOfRange(2, 0))
+        );
+    }
+
+    #[test]
+    fn test_parse_mtl() {
+        let materials = parse_mtl(MTL).unwrap();
+        let red = &materials["red"];
+        let blue = &materials["blue"];
+
+        assert_eq!(red.diffuse, [1.0, 0.0, 0.0]);
+        assert_eq!(red.shininess, 10.0);
+        assert_eq!(blue.dissolve, 0.5);
+        assert_eq!(blue.diffuse_map.as_deref(), Some("textures/blue.png"));
+    }
+
+    #[test]
+    fn test_mtl_errors() {
+        assert_eq!(parse_mtl("Kd 1 1 1\n"), Err(ObjError::NoMaterial(1)));
+        assert_eq!(
+            parse_mtl("newmtl a\nKd 1 1\n"),
+            Err(ObjError::MissingValues(2, 3))
+        );
+    }
+}
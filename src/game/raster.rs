@@ -0,0 +1,287 @@
+/// Vertex in canvas coordinates, `z` is the normalized depth and `w` the clip space `w` used for
+/// perspective correct interpolation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RasterVertex {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub w: f64,
+    pub color: [f64; 3],
+}
+
+/// Software rasterizer writing to an RGBA framebuffer with a depth buffer.
+#[derive(Debug, Clone)]
+pub struct Rasterizer {
+    width: usize,
+    height: usize,
+    color: Vec<u8>,
+    depth: Vec<f64>,
+}
+
+impl Rasterizer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            color: vec![0; width * height * 4],
+            depth: vec![f64::INFINITY; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// RGBA bytes, row by row, ready for `ImageData`.
+    pub fn color_buffer(&self) -> &[u8] {
+        &self.color
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        let i = (y * self.width + x) * 4;
+
+        [
+            self.color[i],
+            self.color[i + 1],
+            self.color[i + 2],
+            self.color[i + 3],
+        ]
+    }
+
+    pub fn depth(&self, x: usize, y: usize) -> f64 {
+        self.depth[y * self.width + x]
+    }
+
+    pub fn clear(&mut self, color: [u8; 4]) {
+        self.clear_color(color);
+        self.depth.fill(f64::INFINITY);
+    }
+
+    /// Clears the colors but keeps the depths, so that the triangles filled next are still hidden
+    /// behind the ones already drawn.
+    pub fn clear_color(&mut self, color: [u8; 4]) {
+        for pixel in self.color.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&color);
+        }
+    }
+
+    /// Fills the pixels whose center is inside the triangle and closer than what is already drawn,
+    /// regardless of the winding.
+    pub fn fill_triangle(&mut self, vertices: &[RasterVertex; 3]) {
+        let [a, b, c] = vertices;
+        let area = edge(a, b, c.x, c.y);
+
+        if area == 0.0 || !area.is_finite() {
+            return;
+        }
+
+        let min_x = a.x.min(b.x).min(c.x).floor().max(0.0) as usize;
+        let min_y = a.y.min(b.y).min(c.y).floor().max(0.0) as usize;
+        let max_x = (a.x.max(b.x).max(c.x).ceil() as usize).min(self.width);
+        let max_y = (a.y.max(b.y).max(c.y).ceil() as usize).min(self.height);
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let (px, py) = (x as f64 + 0.5, y as f64 + 0.5);
+                let l0 = edge(b, c, px, py) / area;
+                let l1 = edge(c, a, px, py) / area;
+                let l2 = edge(a, b, px, py) / area;
+
+                if l0 < 0.0 || l1 < 0.0 || l2 < 0.0 {
+                    continue;
+                }
+
+                // depth is affine in screen space, attributes are not
+                let z = l0 * a.z + l1 * b.z + l2 * c.z;
+                let i = y * self.width + x;
+
+                if !(0.0..=1.0).contains(&z) || z >= self.depth[i] {
+                    continue;
+                }
+
+                let (p0, p1, p2) = (l0 / a.w, l1 / b.w, l2 / c.w);
+                let inv_w = p0 + p1 + p2;
+
+                self.depth[i] = z;
+
+                for channel in 0..3 {
+                    let value =
+                        (p0 * a.color[channel] + p1 * b.color[channel] + p2 * c.color[channel])
+                            / inv_w;
+
+                    self.color[i * 4 + channel] = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+                }
+
+                self.color[i * 4 + 3] = 255;
+            }
+        }
+    }
+}
+
+fn edge(a: &RasterVertex, b: &RasterVertex, x: f64, y: f64) -> f64 {
+    (b.x - a.x) * (y - a.y) - (b.y - a.y) * (x - a.x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertex(x: f64, y: f64, z: f64, color: [f64; 3]) -> RasterVertex {
+        RasterVertex {
+            x,
+            y,
+            z,
+            w: 1.0,
+            color,
+        }
+    }
+
+    fn covered(rasterizer: &Rasterizer) -> usize {
+        rasterizer
+            .color_buffer()
+            .chunks_exact(4)
+            .filter(|pixel| pixel[3] != 0)
+            .count()
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut rasterizer = Rasterizer::new(4, 2);
+
+        rasterizer.clear([1, 2, 3, 4]);
+
+        assert_eq!(rasterizer.color_buffer().len(), 32);
+        assert_eq!(rasterizer.pixel(3, 1), [1, 2, 3, 4]);
+        assert_eq!(rasterizer.depth(3, 1), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_clear_color_keeps_depth() {
+        let mut rasterizer = Rasterizer::new(4, 4);
+        let triangle = |z: f64| {
+            [
+                vertex(0.0, 0.0, z, [1.0; 3]),
+                vertex(4.0, 0.0, z, [1.0; 3]),
+                vertex(0.0, 4.0, z, [1.0; 3]),
+            ]
+        };
+
+        rasterizer.fill_triangle(&triangle(0.25));
+        rasterizer.clear_color([0; 4]);
+        rasterizer.fill_triangle(&triangle(0.5));
+
+        assert_eq!(covered(&rasterizer), 0);
+        assert_eq!(rasterizer.depth(0, 0), 0.25);
+    }
+
+    #[test]
+    fn test_fill_triangle_coverage() {
+        let mut rasterizer = Rasterizer::new(4, 4);
+        let white = [1.0; 3];
+
+        // lower left half of the framebuffer, the diagonal goes through pixel centers
+        rasterizer.fill_triangle(&[
+            vertex(0.0, 0.0, 0.5, white),
+            vertex(0.0, 4.0, 0.5, white),
+            vertex(4.0, 4.0, 0.5, white),
+        ]);
+
+        assert_eq!(covered(&rasterizer), 10);
+        assert_eq!(rasterizer.pixel(0, 3), [255, 255, 255, 255]);
+        assert_eq!(rasterizer.pixel(3, 0), [0, 0, 0, 0]);
+        assert_eq!(rasterizer.depth(0, 3), 0.5);
+    }
+
+    #[test]
+    fn test_winding_does_not_matter() {
+        let mut a = Rasterizer::new(8, 8);
+        let mut b = Rasterizer::new(8, 8);
+        let v = [
+            vertex(1.0, 1.0, 0.5, [1.0; 3]),
+            vertex(7.0, 2.0, 0.5, [1.0; 3]),
+            vertex(3.0, 7.0, 0.5, [1.0; 3]),
+        ];
+
+        a.fill_triangle(&v);
+        b.fill_triangle(&[v[0], v[2], v[1]]);
+
+        assert_eq!(a.color_buffer(), b.color_buffer());
+    }
+
+    #[test]
+    fn test_depth_test() {
+        let quad = |z: f64, color: [f64; 3]| {
+            [
+                [
+                    vertex(0.0, 0.0, z, color),
+                    vertex(4.0, 0.0, z, color),
+                    vertex(4.0, 4.0, z, color),
+                ],
+                [
+                    vertex(0.0, 0.0, z, color),
+                    vertex(4.0, 4.0, z, color),
+                    vertex(0.0, 4.0, z, color),
+                ],
+            ]
+        };
+
+        let near = quad(0.25, [1.0, 0.0, 0.0]);
+        let far = quad(0.75, [0.0, 0.0, 1.0]);
+        let mut a = Rasterizer::new(4, 4);
+        let mut b = Rasterizer::new(4, 4);
+
+        for t in near.iter().chain(far.iter()) {
+            a.fill_triangle(t);
+        }
+
+        for t in far.iter().chain(near.iter()) {
+            b.fill_triangle(t);
+        }
+
+        assert_eq!(a.color_buffer(), b.color_buffer());
+        assert_eq!(a.pixel(2, 2), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_outside_depth_range_is_clipped() {
+        let mut rasterizer = Rasterizer::new(4, 4);
+
+        rasterizer.fill_triangle(&[
+            vertex(0.0, 0.0, 1.5, [1.0; 3]),
+            vertex(4.0, 0.0, 1.5, [1.0; 3]),
+            vertex(0.0, 4.0, 1.5, [1.0; 3]),
+        ]);
+
+        assert_eq!(covered(&rasterizer), 0);
+    }
+
+    #[test]
+    fn test_perspective_correct_interpolation() {
+        let mut rasterizer = Rasterizer::new(4, 1);
+        let (black, white) = ([0.0; 3], [1.0; 3]);
+
+        // the black edge is three times further away than the white vertex, a quarter of the way
+        // across the screen is already half of the way across the triangle
+        rasterizer.fill_triangle(&[
+            RasterVertex {
+                w: 3.0,
+                ..vertex(0.0, -4.0, 0.5, black)
+            },
+            RasterVertex {
+                w: 3.0,
+                ..vertex(0.0, 4.0, 0.5, black)
+            },
+            vertex(6.0, 0.5, 0.5, white),
+        ]);
+
+        let linear = ((0.5 / 6.0) * 255.0_f64).round() as u8;
+        let [r, ..] = rasterizer.pixel(0, 0);
+
+        assert!(r > linear, "expected more than {linear}, got {r}");
+        assert!((127..=128).contains(&rasterizer.pixel(1, 0)[0]));
+    }
+}
@@ -1,123 +1,448 @@
-use std::f64::consts::{FRAC_PI_2, PI};
-
-use wasm_bindgen::{JsValue, UnwrapThrowExt};
-use web_sys::CanvasRenderingContext2d;
-
-use super::math::{
-    new_perspective, new_rotation_x, new_rotation_y, new_rotation_z, new_translation, Mat4, Vec4,
+use super::{
+    backend::{CanvasBackend, Color, RenderBackend},
+    camera::{Camera, ProjectionMode},
+    clip::{clip_line, clip_triangle, ClipPlane, Frustum},
+    light::DirectionalLight,
+    math::{Mat4, Vec3, Vec4},
+    mesh::Mesh,
+    raster::RasterVertex,
+    sprite::Sprite,
 };
 
-pub struct Renderer {
-    context: CanvasRenderingContext2d,
+pub struct Renderer<B: RenderBackend = CanvasBackend> {
+    backend: B,
     width: f64,
     height: f64,
-    fov: f64,
-    z_near: f64,
-    z_far: f64,
-    perspective: Mat4,
+    camera: Camera,
+    view_projection: Mat4,
+    frustum: Frustum,
+    clip_planes: &'static [ClipPlane],
+    light: DirectionalLight,
 }
 
-impl Renderer {
-    pub fn new(context: CanvasRenderingContext2d, width: u32, height: u32) -> Self {
-        let width = width as f64;
-        let height = height as f64;
+impl<B: RenderBackend> Renderer<B> {
+    pub fn new(backend: B) -> Self {
+        let width = backend.width();
+        let height = backend.height();
+
+        let camera = Camera::default();
+        let view_projection = camera.projection(width, height) * camera.view();
 
         Self {
-            context,
+            backend,
             width,
             height,
-            fov: FRAC_PI_2,
-            z_near: 0.1,
-            z_far: 1000.0,
-            perspective: new_perspective(width, height, FRAC_PI_2, 0.1, 1000.0),
+            view_projection,
+            frustum: Frustum::from_view_projection(&view_projection),
+            clip_planes: ClipPlane::NEAR,
+            camera,
+            light: DirectionalLight::default(),
         }
     }
 
-    pub fn clear_frame(&self) {
-        self.context.set_fill_style(&JsValue::from_str("black"));
-        self.context
-            .fill_rect(0.0, 0.0, self.width as f64, self.height as f64);
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    pub fn backend_mut(&mut self) -> &mut B {
+        &mut self.backend
+    }
+
+    pub fn clear_frame(&mut self) {
+        self.backend.clear(Color::BLACK);
+    }
+
+    pub fn present(&mut self) {
+        self.backend.present();
+    }
+
+    /// Clips against every frustum plane instead of only the near plane. The rasterizer already
+    /// discards what falls outside the canvas, this mostly helps backends without a depth buffer.
+    pub fn set_frustum_clipping(&mut self, enabled: bool) {
+        self.clip_planes = if enabled {
+            ClipPlane::ALL
+        } else {
+            ClipPlane::NEAR
+        };
+    }
+
+    pub fn frustum_clipping(&self) -> bool {
+        self.clip_planes == ClipPlane::ALL
+    }
+
+    /// Whether `mesh` may be visible once transformed by `model`.
+    pub fn is_visible(&self, mesh: &Mesh, model: &Mat4) -> bool {
+        self.frustum
+            .intersects_sphere(&mesh.bounding_sphere().transform(model))
     }
 
-    pub fn point(&self, point: impl Into<Vec4>, rotation_x: f64, rotation_y: f64, rotation_z: f64) {
-        let (x, y) = self.transform_2d(point.into(), rotation_x, rotation_y, rotation_z);
+    /// Model view projection of `model` for the current camera, to compose once for the points
+    /// and lines drawn with the same model.
+    pub fn mvp(&self, model: &Mat4) -> Mat4 {
+        self.view_projection * *model
+    }
+
+    pub fn point(&mut self, point: impl Into<Vec4>, mvp: &Mat4) {
+        self.point_clip(*mvp * point.into());
+    }
 
-        self.context.set_fill_style(&JsValue::from_str("white"));
-        self.context.begin_path();
-        self.context.arc(x, y, 5.0, 0.0, PI * 2.0).unwrap_throw();
-        self.context.fill();
-        self.context.close_path();
+    pub fn line(&mut self, p1: impl Into<Vec4>, p2: impl Into<Vec4>, mvp: &Mat4) {
+        self.line_clip(*mvp * p1.into(), *mvp * p2.into());
     }
 
-    pub fn line(
-        &self,
+    pub fn draw_mesh(&mut self, mesh: &Mesh, model: &Mat4) {
+        if !self.is_visible(mesh, model) {
+            return;
+        }
+
+        let mvp = self.mvp(model);
+        let clip: Vec<_> = mesh.vertices.iter().map(|v| mvp * Vec4::from(*v)).collect();
+
+        for [a, b] in mesh.edges() {
+            self.line_clip(clip[a], clip[b]);
+        }
+
+        for vertex in clip {
+            self.point_clip(vertex);
+        }
+    }
+
+    /// Fills a flat shaded triangle, nothing is drawn when it faces away from the camera.
+    pub fn triangle(
+        &mut self,
         p1: impl Into<Vec4>,
         p2: impl Into<Vec4>,
-        rotation_x: f64,
-        rotation_y: f64,
-        rotation_z: f64,
+        p3: impl Into<Vec4>,
+        model: &Mat4,
+        color: [f64; 3],
     ) {
-        let (x1, y1) = self.transform_2d(p1.into(), rotation_x, rotation_y, rotation_z);
-        let (x2, y2) = self.transform_2d(p2.into(), rotation_x, rotation_y, rotation_z);
+        let world = [p1.into(), p2.into(), p3.into()].map(|p| *model * p);
+        let clip = world.map(|p| self.view_projection * p);
+        let color = self
+            .light
+            .lambert(face_normal(&world.map(Vec3::from)), color);
+
+        for clipped in clip_triangle(&clip, self.clip_planes) {
+            let screen = clipped.map(|p| self.project(p));
+
+            if is_front_facing(&screen) {
+                self.fill_projected(&screen, color);
+            }
+        }
+    }
 
-        self.context.set_stroke_style(&JsValue::from_str("pink"));
-        self.context.begin_path();
-        self.context.move_to(x1, y1);
-        self.context.line_to(x2, y2);
-        self.context.stroke();
+    /// Fills every front facing triangle of `mesh`, sorted back to front for backends without a
+    /// depth buffer. Meshes outside of the frustum are skipped and triangles are clipped before
+    /// the perspective divide.
+    pub fn fill_mesh(&mut self, mesh: &Mesh, model: &Mat4, color: [f64; 3]) {
+        if !self.is_visible(mesh, model) {
+            return;
+        }
+
+        let mvp = self.mvp(model);
+        let clip: Vec<_> = mesh.vertices.iter().map(|v| mvp * Vec4::from(*v)).collect();
+
+        let mut faces = Vec::new();
+
+        for triangle in &mesh.triangles {
+            let mut shade = None;
+
+            for clipped in clip_triangle(&triangle.map(|i| clip[i]), self.clip_planes) {
+                let screen = clipped.map(|p| self.project(p));
+
+                if !is_front_facing(&screen) {
+                    continue;
+                }
+
+                let shade = *shade.get_or_insert_with(|| {
+                    let world = triangle.map(|i| Vec3::from(*model * Vec4::from(mesh.vertices[i])));
+
+                    self.light.lambert(face_normal(&world), color)
+                });
+
+                faces.push((screen, shade));
+            }
+        }
+
+        faces.sort_by(|(a, _), (b, _)| {
+            let depth = |s: &[Vec4; 3]| s[0].z() + s[1].z() + s[2].z();
+
+            depth(b).total_cmp(&depth(a))
+        });
+
+        for (screen, shade) in faces {
+            self.fill_projected(&screen, shade);
+        }
+    }
+
+    /// Draws a 2d sprite on top of what has been drawn so far.
+    pub fn draw_sprite(&mut self, image: &B::Image, sprite: &Sprite) {
+        let (destination, transform) = sprite.placement();
+
+        self.backend.draw_image(
+            image,
+            sprite.source,
+            destination,
+            &transform,
+            sprite.alpha.clamp(0.0, 1.0),
+        );
+    }
+
+    /// Draws a filled 2d circle on top of what has been drawn so far, in canvas pixels.
+    pub fn draw_circle(&mut self, x: f64, y: f64, radius: f64, color: Color) {
+        self.backend.draw_point(x, y, radius, color);
+    }
+
+    pub fn set_light(&mut self, light: DirectionalLight) {
+        self.light = light;
+    }
+
+    pub fn light(&self) -> &DirectionalLight {
+        &self.light
+    }
+
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    pub fn update_camera(&mut self, update: impl FnOnce(&mut Camera)) {
+        update(&mut self.camera);
+        self.view_projection = self.camera.projection(self.width, self.height) * self.camera.view();
+        self.frustum = Frustum::from_view_projection(&self.view_projection);
     }
 
     pub fn set_fov(&mut self, fov: f64) {
-        self.fov = fov;
-        self.perspective =
-            new_perspective(self.width, self.height, self.fov, self.z_near, self.z_far);
+        self.update_camera(|camera| camera.fov = fov);
     }
 
     pub fn fov(&self) -> f64 {
-        self.fov
+        self.camera.fov
     }
 
     pub fn set_z_near(&mut self, z_near: f64) {
-        self.z_near = z_near;
-        self.perspective =
-            new_perspective(self.width, self.height, self.fov, self.z_near, self.z_far);
+        self.update_camera(|camera| camera.z_near = z_near);
     }
 
     pub fn z_near(&self) -> f64 {
-        self.z_near
+        self.camera.z_near
     }
 
     pub fn set_z_far(&mut self, z_far: f64) {
-        self.z_far = z_far;
-        self.perspective =
-            new_perspective(self.width, self.height, self.fov, self.z_near, self.z_far);
+        self.update_camera(|camera| camera.z_far = z_far);
     }
 
     pub fn z_far(&self) -> f64 {
-        self.z_far
-    }
-
-    fn transform_2d(
-        &self,
-        point: Vec4,
-        rotation_x: f64,
-        rotation_y: f64,
-        rotation_z: f64,
-    ) -> (f64, f64) {
-        let m1 = new_rotation_x(rotation_x);
-        let m2 = new_rotation_y(rotation_y);
-        let m3 = new_rotation_z(rotation_z);
-        let m5 = new_translation(0.0, 0.0, 3.0);
-
-        let r = point * m1 * m2 * m3 * m5;
-        let r = r * self.perspective;
-        let projection = [[1.0 / r.w(), 0.0, 0.0, 0.0], [0.0, 1.0 / r.w(), 0.0, 0.0]];
-
-        let [x, y] = r * projection;
-
-        (
-            x * 0.5 * self.width + 0.5 * self.width,
-            y * 0.5 * self.height + 0.5 * self.height,
-        )
+        self.camera.z_far
+    }
+
+    pub fn set_projection_mode(&mut self, projection_mode: ProjectionMode) {
+        self.update_camera(|camera| camera.projection_mode = projection_mode);
+    }
+
+    pub fn projection_mode(&self) -> ProjectionMode {
+        self.camera.projection_mode
+    }
+
+    fn point_clip(&mut self, clip: Vec4) {
+        if self
+            .clip_planes
+            .iter()
+            .any(|plane| plane.distance(&clip) < 0.0)
+        {
+            return;
+        }
+
+        let p = self.project(clip);
+
+        self.backend.draw_point(p.x(), p.y(), 5.0, Color::WHITE);
+    }
+
+    fn line_clip(&mut self, a: Vec4, b: Vec4) {
+        if let Some((a, b)) = clip_line(a, b, self.clip_planes) {
+            let (from, to) = (self.project(a), self.project(b));
+
+            self.backend
+                .draw_line((from.x(), from.y()), (to.x(), to.y()), Color::PINK);
+        }
+    }
+
+    fn fill_projected(&mut self, screen: &[Vec4; 3], color: [f64; 3]) {
+        self.backend.fill_triangle(&screen.map(|p| RasterVertex {
+            x: p.x(),
+            y: p.y(),
+            z: p.z(),
+            w: p.w(),
+            color,
+        }));
+    }
+
+    fn project(&self, clip: Vec4) -> Vec4 {
+        project(clip, self.width, self.height)
+    }
+}
+
+/// Divides a clip space position and maps it to canvas coordinates, `z` keeps the normalized depth
+/// and `w` the clip space `w`. The position must be in front of the near plane.
+fn project(r: Vec4, width: f64, height: f64) -> Vec4 {
+    let projection = [[1.0 / r.w(), 0.0, 0.0, 0.0], [0.0, 1.0 / r.w(), 0.0, 0.0]];
+
+    let [x, y] = r * projection;
+
+    Vec4::new(
+        x * 0.5 * width + 0.5 * width,
+        y * 0.5 * height + 0.5 * height,
+        r.z() / r.w(),
+        r.w(),
+    )
+}
+
+/// Front faces have a negative signed area once projected on the canvas.
+fn is_front_facing([a, b, c]: &[Vec4; 3]) -> bool {
+    (b.x() - a.x()) * (c.y() - a.y()) - (b.y() - a.y()) * (c.x() - a.x()) < 0.0
+}
+
+fn face_normal([a, b, c]: &[Vec3; 3]) -> Vec3 {
+    (*b - *a).cross(&(*c - *a))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::backend::{DrawCommand, MemoryBackend, MemoryImage, Rect, Transform2d};
+    use super::*;
+
+    fn to_screen(mvp: &Mat4, point: Vec4, width: f64, height: f64) -> Vec4 {
+        project(*mvp * point, width, height)
+    }
+
+    #[test]
+    fn test_back_face_culling() {
+        let camera = Camera::default();
+        let mvp = camera.projection(480.0, 360.0) * camera.view();
+        let mesh = Mesh::cube(1.0);
+
+        let front_facing: Vec<_> = mesh
+            .triangles
+            .iter()
+            .filter(|t| {
+                is_front_facing(&t.map(|i| to_screen(&mvp, mesh.vertices[i].into(), 480.0, 360.0)))
+            })
+            .map(|t| mesh.normals[t[0]])
+            .collect();
+
+        // the camera looks down +z from z = -3, only the -z face is visible
+        assert_eq!(front_facing, vec![Vec3::new(0.0, 0.0, -1.0); 2]);
+    }
+
+    #[test]
+    fn test_fill_mesh() {
+        let mut renderer = Renderer::new(MemoryBackend::new(48, 36));
+
+        renderer.clear_frame();
+        renderer.fill_mesh(&Mesh::cube(1.0), &Mat4::identity(), [1.0, 1.0, 1.0]);
+        renderer.present();
+
+        let backend = renderer.backend();
+
+        assert_eq!(backend.triangles().count(), 2);
+        assert_eq!(backend.commands.last(), Some(&DrawCommand::Present));
+        assert_ne!(backend.pixel(24, 18), [0, 0, 0, 255]);
+        assert_eq!(backend.pixel(0, 0), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_frustum_culling() {
+        let mut renderer = Renderer::new(MemoryBackend::new(48, 36));
+        let behind = crate::game::math::new_translation(0.0, 0.0, -10.0);
+
+        renderer.fill_mesh(&Mesh::cube(1.0), &behind, [1.0, 1.0, 1.0]);
+        renderer.draw_mesh(&Mesh::cube(1.0), &behind);
+
+        assert!(renderer.backend().commands.is_empty());
+    }
+
+    #[test]
+    fn test_near_plane_clipping() {
+        let mut renderer = Renderer::new(MemoryBackend::new(48, 36));
+
+        // a floor crossing the camera plane would project its far side through infinity
+        renderer.triangle(
+            Vec3::new(-1.0, -0.5, -5.0),
+            Vec3::new(0.0, -0.5, 5.0),
+            Vec3::new(1.0, -0.5, -5.0),
+            &Mat4::identity(),
+            [1.0, 1.0, 1.0],
+        );
+        renderer.line(
+            Vec3::new(0.0, 0.0, -5.0),
+            Vec3::new(0.0, 0.0, 5.0),
+            &renderer.mvp(&Mat4::identity()),
+        );
+
+        let backend = renderer.backend();
+        let triangles: Vec<_> = backend.triangles().collect();
+
+        assert!(!triangles.is_empty());
+
+        for v in triangles.iter().flat_map(|t| t.iter()) {
+            assert!(v.w > 0.0 && v.x.is_finite() && v.y.is_finite());
+            assert!((0.0..=1.0).contains(&v.z));
+        }
+
+        assert!(backend.commands.iter().any(|c| matches!(
+            c,
+            DrawCommand::Line { from, to, .. }
+                if from.0.is_finite() && from.1.is_finite() && to.0.is_finite() && to.1.is_finite()
+        )));
+    }
+
+    #[test]
+    fn test_frustum_clipping_toggle() {
+        let mut renderer = Renderer::new(MemoryBackend::new(48, 36));
+
+        assert!(!renderer.frustum_clipping());
+
+        renderer.set_frustum_clipping(true);
+
+        assert!(renderer.frustum_clipping());
+    }
+
+    #[test]
+    fn test_draw_sprite() {
+        let mut renderer = Renderer::new(MemoryBackend::new(48, 36));
+        let image = MemoryImage {
+            name: "hero".to_string(),
+            width: 64.0,
+            height: 64.0,
+        };
+        let source = Rect::new(0.0, 0.0, 16.0, 16.0);
+
+        renderer.draw_sprite(
+            &image,
+            &Sprite {
+                alpha: 1.5,
+                ..Sprite::new(source, 4.0, 8.0)
+            },
+        );
+
+        assert_eq!(
+            renderer.backend().commands,
+            vec![DrawCommand::Image {
+                name: "hero".to_string(),
+                source,
+                destination: Rect::new(4.0, 8.0, 16.0, 16.0),
+                transform: Transform2d::IDENTITY,
+                alpha: 1.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_to_screen() {
+        let camera = Camera::default();
+        let mvp = camera.projection(480.0, 360.0) * camera.view();
+        let p = to_screen(&mvp, Vec4::new(0.0, 0.0, 0.0, 1.0), 480.0, 360.0);
+
+        assert_eq!((p.x(), p.y()), (240.0, 180.0));
+        assert!(p.z() > 0.0 && p.z() < 1.0);
     }
 }
@@ -0,0 +1,518 @@
+use std::{cell::RefCell, fmt, rc::Rc};
+
+use super::{
+    backend::{CanvasBackend, RenderBackend},
+    input::InputEvent,
+    renderer::Renderer,
+    timestep::FixedTimestep,
+};
+
+/// Screen of the game, such as a title screen, the gameplay or a pause menu, hosted on the stack
+/// of a [`SceneManager`].
+pub trait Scene<B: RenderBackend = CanvasBackend> {
+    /// Called when the scene enters the stack.
+    fn setup(&mut self, _renderer: &mut Renderer<B>) {}
+
+    /// Advances the scene by `dt` seconds, only the top scene is updated.
+    fn update(&mut self, dt: f64, transitions: &mut Transitions<B>);
+
+    /// Draws the scene `alpha` of the way from its previous update to the last one. The frame is
+    /// cleared before the bottom visible scene renders and presented after the top one.
+    fn render(&mut self, renderer: &mut Renderer<B>, alpha: f64);
+
+    /// Only the top scene receives input, returns whether the event was used.
+    fn handle_input(&mut self, _event: &InputEvent, _transitions: &mut Transitions<B>) -> bool {
+        false
+    }
+
+    /// Called when a scene is pushed over this one, which then gets the input instead. By default
+    /// hands the scene a `FocusLost` so that it lets go of what was held, as it would never see
+    /// the releases.
+    fn pause(&mut self) {
+        self.handle_input(&InputEvent::FocusLost, &mut Transitions::default());
+    }
+
+    /// Called when the scene is back on top after the one above it left.
+    fn resume(&mut self) {}
+
+    /// Called when the scene leaves the stack.
+    fn teardown(&mut self) {}
+
+    /// Whether the scenes below stay visible, as under a pause menu. They are rendered but not
+    /// updated.
+    fn is_overlay(&self) -> bool {
+        false
+    }
+}
+
+/// Shared scenes stay reachable from outside the stack.
+impl<B: RenderBackend, S: Scene<B> + ?Sized> Scene<B> for Rc<RefCell<S>> {
+    fn setup(&mut self, renderer: &mut Renderer<B>) {
+        self.borrow_mut().setup(renderer);
+    }
+
+    fn update(&mut self, dt: f64, transitions: &mut Transitions<B>) {
+        self.borrow_mut().update(dt, transitions);
+    }
+
+    fn render(&mut self, renderer: &mut Renderer<B>, alpha: f64) {
+        self.borrow_mut().render(renderer, alpha);
+    }
+
+    fn handle_input(&mut self, event: &InputEvent, transitions: &mut Transitions<B>) -> bool {
+        self.borrow_mut().handle_input(event, transitions)
+    }
+
+    fn pause(&mut self) {
+        self.borrow_mut().pause();
+    }
+
+    fn resume(&mut self) {
+        self.borrow_mut().resume();
+    }
+
+    fn teardown(&mut self) {
+        self.borrow_mut().teardown();
+    }
+
+    fn is_overlay(&self) -> bool {
+        self.borrow().is_overlay()
+    }
+}
+
+pub enum Transition<B: RenderBackend = CanvasBackend> {
+    Push(Box<dyn Scene<B>>),
+    Pop,
+    Replace(Box<dyn Scene<B>>),
+    Clear,
+}
+
+impl<B: RenderBackend> fmt::Debug for Transition<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Transition::Push(_) => "Push",
+            Transition::Pop => "Pop",
+            Transition::Replace(_) => "Replace",
+            Transition::Clear => "Clear",
+        })
+    }
+}
+
+/// Stack changes requested by a scene, applied in order once it returns.
+pub struct Transitions<B: RenderBackend = CanvasBackend> {
+    queue: Vec<Transition<B>>,
+}
+
+impl<B: RenderBackend> Transitions<B> {
+    pub fn push(&mut self, scene: impl Scene<B> + 'static) {
+        self.queue.push(Transition::Push(Box::new(scene)));
+    }
+
+    pub fn pop(&mut self) {
+        self.queue.push(Transition::Pop);
+    }
+
+    pub fn replace(&mut self, scene: impl Scene<B> + 'static) {
+        self.queue.push(Transition::Replace(Box::new(scene)));
+    }
+
+    pub fn clear(&mut self) {
+        self.queue.push(Transition::Clear);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+impl<B: RenderBackend> Default for Transitions<B> {
+    fn default() -> Self {
+        Self { queue: Vec::new() }
+    }
+}
+
+impl<B: RenderBackend> fmt::Debug for Transitions<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(&self.queue).finish()
+    }
+}
+
+/// Stack of scenes driven on a fixed timestep, the top scene is the active one.
+pub struct SceneManager<B: RenderBackend = CanvasBackend> {
+    stack: Vec<Box<dyn Scene<B>>>,
+    timestep: FixedTimestep,
+}
+
+impl<B: RenderBackend> SceneManager<B> {
+    pub fn new() -> Self {
+        Self {
+            stack: Vec::new(),
+            timestep: FixedTimestep::default(),
+        }
+    }
+
+    pub fn push(&mut self, scene: impl Scene<B> + 'static, renderer: &mut Renderer<B>) {
+        self.apply(Transition::Push(Box::new(scene)), renderer);
+    }
+
+    pub fn pop(&mut self, renderer: &mut Renderer<B>) {
+        self.apply(Transition::Pop, renderer);
+    }
+
+    pub fn replace(&mut self, scene: impl Scene<B> + 'static, renderer: &mut Renderer<B>) {
+        self.apply(Transition::Replace(Box::new(scene)), renderer);
+    }
+
+    /// Tears every scene down, from the top.
+    pub fn clear(&mut self) {
+        while let Some(mut scene) = self.stack.pop() {
+            scene.teardown();
+        }
+
+        self.timestep.reset();
+    }
+
+    /// Tears every scene down, from the top, and sets them up again from the bottom, keeping the
+    /// stack as it is.
+    pub fn restart(&mut self, renderer: &mut Renderer<B>) {
+        for scene in self.stack.iter_mut().rev() {
+            scene.teardown();
+        }
+
+        for scene in &mut self.stack {
+            scene.setup(renderer);
+        }
+
+        self.timestep.reset();
+    }
+
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    pub fn timestep(&self) -> &FixedTimestep {
+        &self.timestep
+    }
+
+    pub fn timestep_mut(&mut self) -> &mut FixedTimestep {
+        &mut self.timestep
+    }
+
+    /// Runs the fixed steps due at `time`, the frame timestamp in milliseconds, then renders.
+    pub fn frame(&mut self, renderer: &mut Renderer<B>, time: f64) {
+        let ticks = self.timestep.advance(time);
+        let dt = self.timestep.dt();
+
+        for _ in 0..ticks.steps {
+            let Some(scene) = self.stack.last_mut() else {
+                break;
+            };
+            let mut transitions = Transitions::default();
+
+            scene.update(dt, &mut transitions);
+            self.apply_all(transitions, renderer);
+        }
+
+        self.render(renderer, ticks.alpha);
+    }
+
+    /// Renders the top scene over the overlays below it and the first opaque scene under them.
+    pub fn render(&mut self, renderer: &mut Renderer<B>, alpha: f64) {
+        let bottom = self
+            .stack
+            .iter()
+            .rposition(|scene| !scene.is_overlay())
+            .unwrap_or(0);
+
+        renderer.clear_frame();
+
+        for scene in &mut self.stack[bottom..] {
+            scene.render(renderer, alpha);
+        }
+
+        renderer.present();
+    }
+
+    /// Hands `event` to the top scene, returns whether it was used.
+    pub fn handle_input(&mut self, event: &InputEvent, renderer: &mut Renderer<B>) -> bool {
+        let Some(scene) = self.stack.last_mut() else {
+            return false;
+        };
+        let mut transitions = Transitions::default();
+        let handled = scene.handle_input(event, &mut transitions);
+
+        self.apply_all(transitions, renderer);
+
+        handled
+    }
+
+    fn apply_all(&mut self, transitions: Transitions<B>, renderer: &mut Renderer<B>) {
+        for transition in transitions.queue {
+            self.apply(transition, renderer);
+        }
+    }
+
+    fn apply(&mut self, transition: Transition<B>, renderer: &mut Renderer<B>) {
+        match transition {
+            Transition::Push(mut scene) => {
+                if let Some(covered) = self.stack.last_mut() {
+                    covered.pause();
+                }
+
+                scene.setup(renderer);
+                self.stack.push(scene);
+            }
+            Transition::Pop => {
+                if let Some(mut scene) = self.stack.pop() {
+                    scene.teardown();
+
+                    if let Some(uncovered) = self.stack.last_mut() {
+                        uncovered.resume();
+                    }
+                }
+            }
+            Transition::Replace(mut scene) => {
+                if let Some(mut previous) = self.stack.pop() {
+                    previous.teardown();
+                }
+
+                scene.setup(renderer);
+                self.stack.push(scene);
+            }
+            Transition::Clear => self.clear(),
+        }
+    }
+}
+
+impl<B: RenderBackend> Default for SceneManager<B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B: RenderBackend> fmt::Debug for SceneManager<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SceneManager")
+            .field("len", &self.stack.len())
+            .field("timestep", &self.timestep)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::backend::{DrawCommand, MemoryBackend};
+    use super::*;
+
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    /// Records its calls, pushes a pause overlay on `Escape` and pops itself on `Backspace`.
+    struct TestScene {
+        name: &'static str,
+        overlay: bool,
+        log: Log,
+    }
+
+    impl TestScene {
+        fn new(name: &'static str, log: &Log) -> Self {
+            Self {
+                name,
+                overlay: false,
+                log: log.clone(),
+            }
+        }
+
+        fn record(&self, call: &str) {
+            self.log.borrow_mut().push(format!("{} {call}", self.name));
+        }
+    }
+
+    impl Scene<MemoryBackend> for TestScene {
+        fn setup(&mut self, _renderer: &mut Renderer<MemoryBackend>) {
+            self.record("setup");
+        }
+
+        fn update(&mut self, _dt: f64, _transitions: &mut Transitions<MemoryBackend>) {
+            self.record("update");
+        }
+
+        fn render(&mut self, _renderer: &mut Renderer<MemoryBackend>, _alpha: f64) {
+            self.record("render");
+        }
+
+        fn handle_input(
+            &mut self,
+            event: &InputEvent,
+            transitions: &mut Transitions<MemoryBackend>,
+        ) -> bool {
+            let InputEvent::KeyDown { key, .. } = event else {
+                return false;
+            };
+
+            match key.as_str() {
+                "Escape" => transitions.push(TestScene {
+                    overlay: true,
+                    ..TestScene::new("pause", &self.log)
+                }),
+                "Backspace" => transitions.pop(),
+                _ => return false,
+            }
+
+            true
+        }
+
+        fn pause(&mut self) {
+            self.record("pause");
+        }
+
+        fn resume(&mut self) {
+            self.record("resume");
+        }
+
+        fn teardown(&mut self) {
+            self.record("teardown");
+        }
+
+        fn is_overlay(&self) -> bool {
+            self.overlay
+        }
+    }
+
+    fn key_down(key: &str) -> InputEvent {
+        InputEvent::KeyDown {
+            key: key.to_string(),
+            code: String::new(),
+            repeat: false,
+        }
+    }
+
+    fn take(log: &Log) -> Vec<String> {
+        log.take()
+    }
+
+    #[test]
+    fn test_push_pop_replace() {
+        let log = Log::default();
+        let mut renderer = Renderer::new(MemoryBackend::new(8, 6));
+        let mut scenes = SceneManager::new();
+
+        scenes.push(TestScene::new("title", &log), &mut renderer);
+        scenes.replace(TestScene::new("level", &log), &mut renderer);
+        scenes.push(TestScene::new("pause", &log), &mut renderer);
+
+        assert_eq!(scenes.len(), 2);
+
+        scenes.pop(&mut renderer);
+        scenes.clear();
+
+        assert!(scenes.is_empty());
+        assert_eq!(
+            take(&log),
+            [
+                "title setup",
+                "title teardown",
+                "level setup",
+                "level pause",
+                "pause setup",
+                "pause teardown",
+                "level resume",
+                "level teardown"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_frame_updates_top_and_renders_overlays() {
+        let log = Log::default();
+        let mut renderer = Renderer::new(MemoryBackend::new(8, 6));
+        let mut scenes = SceneManager::new();
+
+        scenes.push(TestScene::new("level", &log), &mut renderer);
+        scenes.timestep_mut().set_tick_rate(100.0);
+
+        assert!(scenes.handle_input(&key_down("Escape"), &mut renderer));
+        assert!(!scenes.handle_input(&key_down("x"), &mut renderer));
+
+        scenes.frame(&mut renderer, 0.0);
+        take(&log);
+        renderer.backend_mut().commands.clear();
+
+        scenes.frame(&mut renderer, 10.0);
+
+        assert_eq!(take(&log), ["pause update", "level render", "pause render"]);
+        assert_eq!(
+            renderer.backend().commands.last(),
+            Some(&DrawCommand::Present)
+        );
+
+        assert!(scenes.handle_input(&key_down("Backspace"), &mut renderer));
+        scenes.frame(&mut renderer, 20.0);
+
+        assert_eq!(
+            take(&log),
+            [
+                "pause teardown",
+                "level resume",
+                "level update",
+                "level render"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_opaque_scene_hides_the_ones_below() {
+        let log = Log::default();
+        let mut renderer = Renderer::new(MemoryBackend::new(8, 6));
+        let mut scenes = SceneManager::new();
+
+        scenes.push(TestScene::new("level", &log), &mut renderer);
+        scenes.push(TestScene::new("inventory", &log), &mut renderer);
+        take(&log);
+
+        scenes.render(&mut renderer, 1.0);
+
+        assert_eq!(take(&log), ["inventory render"]);
+    }
+
+    #[test]
+    fn test_restart_keeps_stack() {
+        let log = Log::default();
+        let mut renderer = Renderer::new(MemoryBackend::new(8, 6));
+        let mut scenes = SceneManager::new();
+
+        scenes.push(TestScene::new("level", &log), &mut renderer);
+        scenes.push(TestScene::new("pause", &log), &mut renderer);
+        take(&log);
+
+        scenes.restart(&mut renderer);
+
+        assert_eq!(scenes.len(), 2);
+        assert_eq!(
+            take(&log),
+            [
+                "pause teardown",
+                "level teardown",
+                "level setup",
+                "pause setup"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shared_scene() {
+        let log = Log::default();
+        let mut renderer = Renderer::new(MemoryBackend::new(8, 6));
+        let mut scenes = SceneManager::new();
+        let scene = Rc::new(RefCell::new(TestScene::new("level", &log)));
+
+        scenes.push(scene.clone(), &mut renderer);
+        scene.borrow_mut().name = "renamed";
+        scenes.render(&mut renderer, 1.0);
+
+        assert_eq!(take(&log), ["level setup", "renamed render"]);
+    }
+}
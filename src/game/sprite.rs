@@ -0,0 +1,366 @@
+use std::{collections::BTreeMap, f64::consts::FRAC_PI_2, str::FromStr};
+
+use serde::Deserialize;
+
+use super::backend::{Rect, Transform2d};
+
+/// Placement of an image region on the canvas.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprite {
+    pub source: Rect,
+    pub destination: Rect,
+    /// Clockwise, in radians, around `origin`.
+    pub rotation: f64,
+    pub scale: (f64, f64),
+    pub flip_x: bool,
+    pub flip_y: bool,
+    pub alpha: f64,
+    /// Pivot for rotation, scale and flip, relative to `destination` (`(0.5, 0.5)` is its center).
+    pub origin: (f64, f64),
+    /// The source region is stored rotated 90° clockwise, as packed atlases do.
+    pub source_rotated: bool,
+}
+
+impl Sprite {
+    /// Draws `source` unscaled with its top left corner at `(x, y)`.
+    pub fn new(source: Rect, x: f64, y: f64) -> Self {
+        Self {
+            source,
+            destination: Rect::new(x, y, source.width, source.height),
+            rotation: 0.0,
+            scale: (1.0, 1.0),
+            flip_x: false,
+            flip_y: false,
+            alpha: 1.0,
+            origin: (0.5, 0.5),
+            source_rotated: false,
+        }
+    }
+
+    /// Transform applied to `destination`.
+    pub fn transform(&self) -> Transform2d {
+        let d = &self.destination;
+        let pivot = (
+            d.x + self.origin.0 * d.width,
+            d.y + self.origin.1 * d.height,
+        );
+        let flip = |flipped: bool| if flipped { -1.0 } else { 1.0 };
+
+        Transform2d::translation(pivot.0, pivot.1)
+            * Transform2d::rotation(self.rotation)
+            * Transform2d::scale(
+                self.scale.0 * flip(self.flip_x),
+                self.scale.1 * flip(self.flip_y),
+            )
+            * Transform2d::translation(-pivot.0, -pivot.1)
+    }
+
+    /// Destination and transform to hand to the backend, undoing the atlas rotation if needed.
+    pub fn placement(&self) -> (Rect, Transform2d) {
+        let transform = self.transform();
+
+        if !self.source_rotated {
+            return (self.destination, transform);
+        }
+
+        let d = &self.destination;
+        let center = (d.x + d.width * 0.5, d.y + d.height * 0.5);
+
+        (
+            Rect::new(
+                center.0 - d.height * 0.5,
+                center.1 - d.width * 0.5,
+                d.height,
+                d.width,
+            ),
+            transform
+                * Transform2d::translation(center.0, center.1)
+                * Transform2d::rotation(-FRAC_PI_2)
+                * Transform2d::translation(-center.0, -center.1),
+        )
+    }
+}
+
+/// Frame of a texture atlas. Trimmed frames only store the opaque part of the original image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AtlasFrame {
+    /// Region of the atlas image, with its width and height swapped when `rotated`.
+    pub source: Rect,
+    pub rotated: bool,
+    /// Position of the stored part within the original image.
+    pub offset: (f64, f64),
+    /// Size of the stored part, before rotation.
+    pub trimmed_size: (f64, f64),
+    /// Size of the original image.
+    pub size: (f64, f64),
+}
+
+impl AtlasFrame {
+    /// Sprite drawing the original image with its top left corner at `(x, y)`, pivoting around
+    /// the center of the original image.
+    pub fn sprite(&self, x: f64, y: f64) -> Sprite {
+        let (width, height) = self.trimmed_size;
+
+        Sprite {
+            destination: Rect::new(x + self.offset.0, y + self.offset.1, width, height),
+            origin: (
+                (self.size.0 * 0.5 - self.offset.0) / width,
+                (self.size.1 * 0.5 - self.offset.1) / height,
+            ),
+            source_rotated: self.rotated,
+            ..Sprite::new(self.source, x, y)
+        }
+    }
+}
+
+/// Sprite sheet described by a TexturePacker JSON file, in either the hash or array format.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextureAtlas {
+    /// Image file of the atlas, relative to the JSON file.
+    pub image: Option<String>,
+    frames: BTreeMap<String, AtlasFrame>,
+}
+
+impl TextureAtlas {
+    pub fn frame(&self, name: &str) -> Option<&AtlasFrame> {
+        self.frames.get(name)
+    }
+
+    pub fn sprite(&self, name: &str, x: f64, y: f64) -> Option<Sprite> {
+        self.frame(name).map(|frame| frame.sprite(x, y))
+    }
+
+    /// Frame names in lexicographic order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.frames.keys().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+impl FromStr for TextureAtlas {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let atlas: RawAtlas = serde_json::from_str(s)?;
+        let frames = match atlas.frames {
+            RawFrames::Hash(frames) => frames,
+            RawFrames::Array(frames) => frames
+                .into_iter()
+                .map(|frame| (frame.filename, frame.frame))
+                .collect(),
+        };
+
+        Ok(Self {
+            image: atlas.meta.and_then(|meta| meta.image),
+            frames: frames
+                .into_iter()
+                .map(|(name, frame)| (name, frame.into()))
+                .collect(),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct RawAtlas {
+    frames: RawFrames,
+    meta: Option<RawMeta>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawFrames {
+    Hash(BTreeMap<String, RawFrame>),
+    Array(Vec<RawNamedFrame>),
+}
+
+#[derive(Deserialize)]
+struct RawNamedFrame {
+    filename: String,
+    #[serde(flatten)]
+    frame: RawFrame,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawFrame {
+    frame: RawRect,
+    #[serde(default)]
+    rotated: bool,
+    sprite_source_size: Option<RawRect>,
+    source_size: Option<RawSize>,
+}
+
+#[derive(Deserialize)]
+struct RawRect {
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+}
+
+#[derive(Deserialize)]
+struct RawSize {
+    w: f64,
+    h: f64,
+}
+
+#[derive(Deserialize)]
+struct RawMeta {
+    image: Option<String>,
+}
+
+impl From<RawFrame> for AtlasFrame {
+    fn from(raw: RawFrame) -> Self {
+        let RawRect { x, y, w, h } = raw.frame;
+        let trim = raw.sprite_source_size.unwrap_or(RawRect {
+            x: 0.0,
+            y: 0.0,
+            w,
+            h,
+        });
+        let size = raw.source_size.unwrap_or(RawSize { w, h });
+
+        Self {
+            source: if raw.rotated {
+                Rect::new(x, y, h, w)
+            } else {
+                Rect::new(x, y, w, h)
+            },
+            rotated: raw.rotated,
+            offset: (trim.x, trim.y),
+            trimmed_size: (w, h),
+            size: (size.w, size.h),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = r#"{
+        "frames": {
+            "hero_0.png": {
+                "frame": { "x": 2, "y": 2, "w": 32, "h": 48 },
+                "rotated": false,
+                "trimmed": false,
+                "spriteSourceSize": { "x": 0, "y": 0, "w": 32, "h": 48 },
+                "sourceSize": { "w": 32, "h": 48 }
+            },
+            "hero_1.png": {
+                "frame": { "x": 36, "y": 2, "w": 20, "h": 40 },
+                "rotated": true,
+                "trimmed": true,
+                "spriteSourceSize": { "x": 6, "y": 4, "w": 20, "h": 40 },
+                "sourceSize": { "w": 32, "h": 48 }
+            }
+        },
+        "meta": { "image": "hero.png", "size": { "w": 128, "h": 64 } }
+    }"#;
+
+    fn assert_point_approx_eq((x1, y1): (f64, f64), (x2, y2): (f64, f64)) {
+        assert!(
+            (x1 - x2).abs() < 1e-9 && (y1 - y2).abs() < 1e-9,
+            "{:?} != {:?}",
+            (x1, y1),
+            (x2, y2)
+        );
+    }
+
+    #[test]
+    fn test_parse_hash() {
+        let atlas: TextureAtlas = HASH.parse().unwrap();
+
+        assert_eq!(atlas.image.as_deref(), Some("hero.png"));
+        assert_eq!(
+            atlas.names().collect::<Vec<_>>(),
+            ["hero_0.png", "hero_1.png"]
+        );
+        assert_eq!(
+            atlas.frame("hero_1.png"),
+            Some(&AtlasFrame {
+                source: Rect::new(36.0, 2.0, 40.0, 20.0),
+                rotated: true,
+                offset: (6.0, 4.0),
+                trimmed_size: (20.0, 40.0),
+                size: (32.0, 48.0),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_array() {
+        let atlas: TextureAtlas = r#"{
+            "frames": [
+                { "filename": "coin", "frame": { "x": 0, "y": 0, "w": 16, "h": 16 } }
+            ]
+        }"#
+        .parse()
+        .unwrap();
+
+        assert_eq!(atlas.len(), 1);
+        assert_eq!(atlas.image, None);
+        assert_eq!(
+            atlas.sprite("coin", 10.0, 20.0).map(|s| s.destination),
+            Some(Rect::new(10.0, 20.0, 16.0, 16.0))
+        );
+        assert!("{\"frames\": 1}".parse::<TextureAtlas>().is_err());
+    }
+
+    #[test]
+    fn test_sprite_transform() {
+        let sprite = Sprite {
+            scale: (2.0, 2.0),
+            flip_x: true,
+            ..Sprite::new(Rect::new(0.0, 0.0, 10.0, 10.0), 100.0, 100.0)
+        };
+        let transform = sprite.transform();
+
+        // scaled and mirrored around the center, the top left corner ends up top right
+        assert_point_approx_eq(transform.apply((105.0, 105.0)), (105.0, 105.0));
+        assert_point_approx_eq(transform.apply((100.0, 100.0)), (115.0, 95.0));
+    }
+
+    #[test]
+    fn test_rotated_frame_placement() {
+        let atlas: TextureAtlas = HASH.parse().unwrap();
+        let sprite = atlas.sprite("hero_1.png", 0.0, 0.0).unwrap();
+        let (destination, transform) = sprite.placement();
+
+        assert_eq!(destination.width, sprite.source.width);
+        assert_eq!(destination.height, sprite.source.height);
+
+        // the stored region is rotated back into the 20x40 trimmed area at (6, 4)
+        let corners = [
+            (destination.x, destination.y),
+            (
+                destination.x + destination.width,
+                destination.y + destination.height,
+            ),
+        ]
+        .map(|p| transform.apply(p));
+
+        assert_point_approx_eq(corners[0], (6.0, 44.0));
+        assert_point_approx_eq(corners[1], (26.0, 4.0));
+    }
+
+    #[test]
+    fn test_trimmed_frame_pivot() {
+        let atlas: TextureAtlas = HASH.parse().unwrap();
+        let sprite = Sprite {
+            rotation: std::f64::consts::PI,
+            ..atlas.sprite("hero_1.png", 0.0, 0.0).unwrap()
+        };
+
+        // rotating half a turn around the center of the untrimmed 32x48 image
+        assert_point_approx_eq(sprite.transform().apply((16.0, 24.0)), (16.0, 24.0));
+        assert_point_approx_eq(sprite.transform().apply((6.0, 4.0)), (26.0, 44.0));
+    }
+}
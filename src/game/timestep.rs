@@ -0,0 +1,127 @@
+/// Steps to simulate for a frame, `alpha` is how far the frame is between the last two steps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ticks {
+    pub steps: u32,
+    pub alpha: f64,
+}
+
+/// Accumulates frame time and hands it out in fixed steps, so the simulation runs at the same
+/// speed whatever the display refresh rate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedTimestep {
+    tick_rate: f64,
+    /// Steps simulated at most per frame, time beyond that is dropped instead of catching up.
+    pub max_steps: u32,
+    accumulator: f64,
+    last_time: Option<f64>,
+}
+
+impl FixedTimestep {
+    /// `tick_rate` is in steps per second.
+    pub fn new(tick_rate: f64, max_steps: u32) -> Self {
+        Self {
+            tick_rate: tick_rate.max(f64::EPSILON),
+            max_steps,
+            accumulator: 0.0,
+            last_time: None,
+        }
+    }
+
+    pub fn tick_rate(&self) -> f64 {
+        self.tick_rate
+    }
+
+    pub fn set_tick_rate(&mut self, tick_rate: f64) {
+        self.tick_rate = tick_rate.max(f64::EPSILON);
+    }
+
+    /// Duration of a step in seconds.
+    pub fn dt(&self) -> f64 {
+        1.0 / self.tick_rate
+    }
+
+    /// `time` is the frame timestamp in milliseconds, the first frame runs no step.
+    pub fn advance(&mut self, time: f64) -> Ticks {
+        let delta = (time - self.last_time.unwrap_or(time)).max(0.0) / 1000.0;
+        let dt = self.dt();
+
+        self.last_time = Some(time);
+        self.accumulator += delta;
+
+        // frame times rarely add up to an exact multiple of the step
+        let mut steps = (self.accumulator / dt + 1e-9).floor() as u32;
+
+        if steps > self.max_steps {
+            steps = self.max_steps;
+            self.accumulator %= dt;
+        } else {
+            self.accumulator = (self.accumulator - steps as f64 * dt).max(0.0);
+        }
+
+        Ticks {
+            steps,
+            alpha: (self.accumulator / dt).clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.accumulator = 0.0;
+        self.last_time = None;
+    }
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        Self::new(60.0, 5)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_advance() {
+        let mut timestep = FixedTimestep::new(50.0, 5);
+
+        assert_eq!(timestep.advance(1000.0).steps, 0);
+
+        let ticks = timestep.advance(1030.0);
+
+        assert_eq!(ticks.steps, 1);
+        assert!((ticks.alpha - 0.5).abs() < 1e-9);
+
+        let ticks = timestep.advance(1040.0);
+
+        assert_eq!(ticks.steps, 1);
+        assert!(ticks.alpha.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_max_steps() {
+        let mut timestep = FixedTimestep::new(100.0, 3);
+
+        timestep.advance(0.0);
+
+        let ticks = timestep.advance(1005.0);
+
+        assert_eq!(ticks.steps, 3);
+        assert!((ticks.alpha - 0.5).abs() < 1e-6);
+        assert_eq!(timestep.advance(1015.0).steps, 1);
+    }
+
+    #[test]
+    fn test_steps_do_not_depend_on_refresh_rate() {
+        let steps = |hz: f64| {
+            let mut timestep = FixedTimestep::default();
+
+            (0..=hz as u32)
+                .map(|frame| timestep.advance(frame as f64 * 1000.0 / hz).steps)
+                .sum::<u32>()
+        };
+
+        assert!((59..=60).contains(&steps(60.0)));
+        assert!((59..=60).contains(&steps(144.0)));
+        assert!((59..=60).contains(&steps(30.0)));
+    }
+}
@@ -0,0 +1,639 @@
+//! Touch pointers turned into gestures, and the on-screen joystick standing in for the arrow keys.
+
+use std::{collections::BTreeMap, f64::consts::PI};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    backend::{Color, RenderBackend},
+    input::{InputEvent, Pointer, PointerKind},
+    renderer::Renderer,
+};
+
+/// Canvas pixels a touch may move and still be a tap.
+const TAP_SLOP: f64 = 10.0;
+
+/// Milliseconds a touch may last and still be a tap.
+const TAP_DURATION: f64 = 300.0;
+
+/// Milliseconds between the two taps of a double tap.
+const DOUBLE_TAP_INTERVAL: f64 = 300.0;
+
+/// Canvas pixels between the two taps of a double tap.
+const DOUBLE_TAP_SLOP: f64 = 30.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GesturePhase {
+    Start,
+    Change,
+    End,
+}
+
+/// Gesture recognized from the touches, positions and distances are in canvas pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Gesture {
+    Tap {
+        x: f64,
+        y: f64,
+    },
+    /// Second of two taps close in time and space, it follows the `Tap` of the second touch.
+    DoubleTap {
+        x: f64,
+        y: f64,
+    },
+    /// One finger moved by `(dx, dy)` since the last drag gesture.
+    Drag {
+        phase: GesturePhase,
+        dx: f64,
+        dy: f64,
+    },
+    /// Two fingers, `scale` is the ratio of their distance to the one at the last pinch gesture.
+    Pinch {
+        phase: GesturePhase,
+        scale: f64,
+    },
+    /// Two fingers, `angle` is the radians they turned clockwise since the last rotate gesture.
+    Rotate {
+        phase: GesturePhase,
+        angle: f64,
+    },
+}
+
+/// Gesture an action can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GestureKind {
+    Tap,
+    DoubleTap,
+    Drag,
+    Pinch,
+    Rotate,
+}
+
+impl Gesture {
+    pub fn kind(&self) -> GestureKind {
+        match self {
+            Gesture::Tap { .. } => GestureKind::Tap,
+            Gesture::DoubleTap { .. } => GestureKind::DoubleTap,
+            Gesture::Drag { .. } => GestureKind::Drag,
+            Gesture::Pinch { .. } => GestureKind::Pinch,
+            Gesture::Rotate { .. } => GestureKind::Rotate,
+        }
+    }
+
+    /// `None` for taps, which have no duration.
+    pub fn phase(&self) -> Option<GesturePhase> {
+        match self {
+            Gesture::Tap { .. } | Gesture::DoubleTap { .. } => None,
+            Gesture::Drag { phase, .. }
+            | Gesture::Pinch { phase, .. }
+            | Gesture::Rotate { phase, .. } => Some(*phase),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Touch {
+    start: (f64, f64),
+    start_time: f64,
+    position: (f64, f64),
+}
+
+/// Tracks the touch pointers on the canvas and recognizes gestures from them. Mouse and pen
+/// pointers are ignored.
+#[derive(Debug, Default)]
+pub struct GestureRecognizer {
+    touches: BTreeMap<i32, Touch>,
+    dragging: bool,
+    /// Distance and angle between the first two touches at the last pinch and rotate gestures.
+    pair: Option<(f64, f64)>,
+    /// Whether the touch down can still be a tap, it cannot once a second finger came down.
+    tap_candidate: bool,
+    /// Position and time of the last tap not part of a double tap.
+    last_tap: Option<(f64, f64, f64)>,
+}
+
+impl GestureRecognizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of fingers down.
+    pub fn touches(&self) -> usize {
+        self.touches.len()
+    }
+
+    /// Forgets the touches without ending their gestures.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Returns the gestures `event` starts, changes or ends.
+    pub fn handle(&mut self, event: &InputEvent) -> Vec<Gesture> {
+        match event {
+            InputEvent::PointerDown { pointer, .. } if pointer.kind == PointerKind::Touch => {
+                self.touch_down(pointer)
+            }
+            InputEvent::PointerMove { pointer, .. } if pointer.kind == PointerKind::Touch => {
+                self.touch_move(pointer)
+            }
+            InputEvent::PointerUp { pointer, .. } if pointer.kind == PointerKind::Touch => {
+                self.touch_up(pointer, true)
+            }
+            InputEvent::PointerCancel { pointer } if pointer.kind == PointerKind::Touch => {
+                self.touch_up(pointer, false)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn touch_down(&mut self, pointer: &Pointer) -> Vec<Gesture> {
+        let mut gestures = Vec::new();
+
+        self.touches.insert(
+            pointer.id,
+            Touch {
+                start: (pointer.x, pointer.y),
+                start_time: pointer.time,
+                position: (pointer.x, pointer.y),
+            },
+        );
+
+        match self.touches.len() {
+            1 => self.tap_candidate = true,
+            2 => {
+                self.tap_candidate = false;
+
+                if self.dragging {
+                    self.dragging = false;
+                    gestures.push(Gesture::Drag {
+                        phase: GesturePhase::End,
+                        dx: 0.0,
+                        dy: 0.0,
+                    });
+                }
+
+                self.pair = self.pair_geometry();
+                gestures.push(Gesture::Pinch {
+                    phase: GesturePhase::Start,
+                    scale: 1.0,
+                });
+                gestures.push(Gesture::Rotate {
+                    phase: GesturePhase::Start,
+                    angle: 0.0,
+                });
+            }
+            _ => self.pair = self.pair_geometry(),
+        }
+
+        gestures
+    }
+
+    fn touch_move(&mut self, pointer: &Pointer) -> Vec<Gesture> {
+        let Some(touch) = self.touches.get_mut(&pointer.id) else {
+            return Vec::new();
+        };
+        let previous = touch.position;
+
+        touch.position = (pointer.x, pointer.y);
+
+        let touch = *touch;
+
+        if self.touches.len() > 1 {
+            return self.pair_changed();
+        }
+
+        if self.dragging {
+            return vec![Gesture::Drag {
+                phase: GesturePhase::Change,
+                dx: touch.position.0 - previous.0,
+                dy: touch.position.1 - previous.1,
+            }];
+        }
+
+        if distance(touch.start, touch.position) <= TAP_SLOP {
+            return Vec::new();
+        }
+
+        self.dragging = true;
+        self.tap_candidate = false;
+
+        vec![Gesture::Drag {
+            phase: GesturePhase::Start,
+            dx: touch.position.0 - touch.start.0,
+            dy: touch.position.1 - touch.start.1,
+        }]
+    }
+
+    /// `lifted` is false when the browser cancelled the touch, which then is no tap.
+    fn touch_up(&mut self, pointer: &Pointer, lifted: bool) -> Vec<Gesture> {
+        let Some(touch) = self.touches.remove(&pointer.id) else {
+            return Vec::new();
+        };
+        let mut gestures = Vec::new();
+
+        match self.touches.len() {
+            0 if self.dragging => {
+                self.dragging = false;
+                gestures.push(Gesture::Drag {
+                    phase: GesturePhase::End,
+                    dx: 0.0,
+                    dy: 0.0,
+                });
+            }
+            0 if lifted
+                && self.tap_candidate
+                && pointer.time - touch.start_time <= TAP_DURATION =>
+            {
+                let (x, y) = touch.start;
+
+                gestures.push(Gesture::Tap { x, y });
+
+                match self.last_tap {
+                    Some((last_x, last_y, time))
+                        if pointer.time - time <= DOUBLE_TAP_INTERVAL
+                            && distance((last_x, last_y), (x, y)) <= DOUBLE_TAP_SLOP =>
+                    {
+                        self.last_tap = None;
+                        gestures.push(Gesture::DoubleTap { x, y });
+                    }
+                    _ => self.last_tap = Some((x, y, pointer.time)),
+                }
+            }
+            0 => {}
+            1 => {
+                self.pair = None;
+                gestures.push(Gesture::Pinch {
+                    phase: GesturePhase::End,
+                    scale: 1.0,
+                });
+                gestures.push(Gesture::Rotate {
+                    phase: GesturePhase::End,
+                    angle: 0.0,
+                });
+
+                // the finger left down may start a drag from where it is
+                for touch in self.touches.values_mut() {
+                    touch.start = touch.position;
+                    touch.start_time = pointer.time;
+                }
+            }
+            _ => self.pair = self.pair_geometry(),
+        }
+
+        gestures
+    }
+
+    fn pair_changed(&mut self) -> Vec<Gesture> {
+        let (Some((distance, angle)), Some(pair)) = (self.pair, self.pair_geometry()) else {
+            return Vec::new();
+        };
+
+        self.pair = Some(pair);
+
+        vec![
+            Gesture::Pinch {
+                phase: GesturePhase::Change,
+                scale: if distance > 0.0 {
+                    pair.0 / distance
+                } else {
+                    1.0
+                },
+            },
+            Gesture::Rotate {
+                phase: GesturePhase::Change,
+                angle: wrap_angle(pair.1 - angle),
+            },
+        ]
+    }
+
+    fn pair_geometry(&self) -> Option<(f64, f64)> {
+        let mut touches = self.touches.values();
+        let (a, b) = (touches.next()?.position, touches.next()?.position);
+
+        Some((distance(a, b), (b.1 - a.1).atan2(b.0 - a.0)))
+    }
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (b.0 - a.0).hypot(b.1 - a.1)
+}
+
+/// `angle` brought between -PI and PI.
+fn wrap_angle(angle: f64) -> f64 {
+    (angle + PI).rem_euclid(2.0 * PI) - PI
+}
+
+/// On-screen joystick moved by a touch landing on its base.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VirtualJoystick {
+    /// Center of the base in canvas pixels.
+    pub center: (f64, f64),
+    pub radius: f64,
+    /// Touch moving the knob.
+    pointer: Option<i32>,
+    /// Knob offset from the center, at most `radius` long.
+    knob: (f64, f64),
+}
+
+impl VirtualJoystick {
+    pub fn new(center: (f64, f64), radius: f64) -> Self {
+        Self {
+            center,
+            radius,
+            pointer: None,
+            knob: (0.0, 0.0),
+        }
+    }
+
+    /// Joystick in the bottom left corner of a canvas of `width` by `height` pixels.
+    pub fn bottom_left(width: f64, height: f64) -> Self {
+        let radius = width.min(height) / 8.0;
+
+        Self::new((radius * 1.5, height - radius * 1.5), radius)
+    }
+
+    /// Takes a touch landing on the base and moves the knob with it, returns whether `event` was
+    /// used.
+    pub fn handle(&mut self, event: &InputEvent) -> bool {
+        match event {
+            InputEvent::PointerDown { pointer, .. }
+                if pointer.kind == PointerKind::Touch
+                    && self.pointer.is_none()
+                    && distance(self.center, (pointer.x, pointer.y)) <= self.radius =>
+            {
+                self.pointer = Some(pointer.id);
+                self.move_knob(pointer);
+                true
+            }
+            InputEvent::PointerMove { pointer, .. } if self.pointer == Some(pointer.id) => {
+                self.move_knob(pointer);
+                true
+            }
+            InputEvent::PointerUp { pointer, .. } | InputEvent::PointerCancel { pointer }
+                if self.pointer == Some(pointer.id) =>
+            {
+                self.reset();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.pointer.is_some()
+    }
+
+    /// Knob position as x and y between -1 and 1, y pointing up.
+    pub fn value(&self) -> (f64, f64) {
+        if self.radius > 0.0 {
+            (self.knob.0 / self.radius, -self.knob.1 / self.radius)
+        } else {
+            (0.0, 0.0)
+        }
+    }
+
+    /// Releases the touch and recenters the knob.
+    pub fn reset(&mut self) {
+        self.pointer = None;
+        self.knob = (0.0, 0.0);
+    }
+
+    /// Draws the base and the knob on top of what has been drawn so far.
+    pub fn draw<B: RenderBackend>(&self, renderer: &mut Renderer<B>) {
+        let (x, y) = self.center;
+        let alpha = if self.is_active() { 0.6 } else { 0.3 };
+
+        renderer.draw_circle(x, y, self.radius, Color([1.0, 1.0, 1.0, alpha / 2.0]));
+        renderer.draw_circle(
+            x + self.knob.0,
+            y + self.knob.1,
+            self.radius * 0.4,
+            Color([1.0, 1.0, 1.0, alpha]),
+        );
+    }
+
+    fn move_knob(&mut self, pointer: &Pointer) {
+        let offset = (pointer.x - self.center.0, pointer.y - self.center.1);
+        let length = offset.0.hypot(offset.1);
+        let scale = if length > self.radius {
+            self.radius / length
+        } else {
+            1.0
+        };
+
+        self.knob = (offset.0 * scale, offset.1 * scale);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use super::super::backend::{DrawCommand, MemoryBackend};
+    use super::*;
+
+    fn touch(id: i32, x: f64, y: f64, time: f64) -> Pointer {
+        Pointer {
+            id,
+            kind: PointerKind::Touch,
+            x,
+            y,
+            time,
+        }
+    }
+
+    fn down(id: i32, x: f64, y: f64, time: f64) -> InputEvent {
+        InputEvent::PointerDown {
+            pointer: touch(id, x, y, time),
+            button: 0,
+        }
+    }
+
+    fn up(id: i32, x: f64, y: f64, time: f64) -> InputEvent {
+        InputEvent::PointerUp {
+            pointer: touch(id, x, y, time),
+            button: 0,
+        }
+    }
+
+    fn touch_move(id: i32, x: f64, y: f64, time: f64) -> InputEvent {
+        InputEvent::PointerMove {
+            pointer: touch(id, x, y, time),
+            movement_x: 0.0,
+            movement_y: 0.0,
+            buttons: 1,
+        }
+    }
+
+    #[test]
+    fn test_tap_and_double_tap() {
+        let mut gestures = GestureRecognizer::new();
+
+        assert!(gestures.handle(&down(1, 10.0, 10.0, 0.0)).is_empty());
+        assert!(gestures.handle(&touch_move(1, 13.0, 10.0, 50.0)).is_empty());
+        assert_eq!(
+            gestures.handle(&up(1, 13.0, 10.0, 100.0)),
+            [Gesture::Tap { x: 10.0, y: 10.0 }]
+        );
+
+        gestures.handle(&down(2, 20.0, 10.0, 250.0));
+
+        assert_eq!(
+            gestures.handle(&up(2, 20.0, 10.0, 300.0)),
+            [
+                Gesture::Tap { x: 20.0, y: 10.0 },
+                Gesture::DoubleTap { x: 20.0, y: 10.0 }
+            ]
+        );
+
+        // too long for a tap
+        gestures.handle(&down(3, 20.0, 10.0, 1000.0));
+
+        assert!(gestures.handle(&up(3, 20.0, 10.0, 1400.0)).is_empty());
+
+        // cancelled by the browser
+        gestures.handle(&down(4, 20.0, 10.0, 2000.0));
+
+        assert!(gestures
+            .handle(&InputEvent::PointerCancel {
+                pointer: touch(4, 20.0, 10.0, 2050.0),
+            })
+            .is_empty());
+        assert_eq!(gestures.touches(), 0);
+    }
+
+    #[test]
+    fn test_drag() {
+        let mut gestures = GestureRecognizer::new();
+
+        gestures.handle(&down(1, 10.0, 10.0, 0.0));
+
+        assert_eq!(
+            gestures.handle(&touch_move(1, 25.0, 10.0, 10.0)),
+            [Gesture::Drag {
+                phase: GesturePhase::Start,
+                dx: 15.0,
+                dy: 0.0,
+            }]
+        );
+        assert_eq!(
+            gestures.handle(&touch_move(1, 25.0, 5.0, 20.0)),
+            [Gesture::Drag {
+                phase: GesturePhase::Change,
+                dx: 0.0,
+                dy: -5.0,
+            }]
+        );
+        assert_eq!(
+            gestures.handle(&up(1, 25.0, 5.0, 30.0)),
+            [Gesture::Drag {
+                phase: GesturePhase::End,
+                dx: 0.0,
+                dy: 0.0,
+            }]
+        );
+
+        // mice drag through the pointer events
+        assert!(gestures
+            .handle(&InputEvent::PointerDown {
+                pointer: Pointer {
+                    kind: PointerKind::Mouse,
+                    ..touch(2, 0.0, 0.0, 0.0)
+                },
+                button: 0,
+            })
+            .is_empty());
+        assert_eq!(gestures.touches(), 0);
+    }
+
+    #[test]
+    fn test_pinch_and_rotate() {
+        let mut gestures = GestureRecognizer::new();
+
+        gestures.handle(&down(1, 0.0, 0.0, 0.0));
+
+        assert_eq!(
+            gestures.handle(&down(2, 10.0, 0.0, 10.0)),
+            [
+                Gesture::Pinch {
+                    phase: GesturePhase::Start,
+                    scale: 1.0,
+                },
+                Gesture::Rotate {
+                    phase: GesturePhase::Start,
+                    angle: 0.0,
+                }
+            ]
+        );
+
+        // the second finger swings down and away, a quarter turn clockwise on screen
+        let changes = gestures.handle(&touch_move(2, 0.0, 20.0, 20.0));
+
+        assert!(matches!(
+            changes[0],
+            Gesture::Pinch { phase: GesturePhase::Change, scale } if (scale - 2.0).abs() < 1e-9
+        ));
+        assert!(matches!(
+            changes[1],
+            Gesture::Rotate { phase: GesturePhase::Change, angle }
+                if (angle - FRAC_PI_2).abs() < 1e-9
+        ));
+
+        let ends = gestures.handle(&up(1, 0.0, 0.0, 30.0));
+
+        assert_eq!(ends.len(), 2);
+        assert!(ends
+            .iter()
+            .all(|gesture| gesture.phase() == Some(GesturePhase::End)));
+
+        // the finger left drags from where it is, and does not tap
+        assert!(gestures.handle(&touch_move(2, 5.0, 20.0, 40.0)).is_empty());
+        assert!(gestures.handle(&up(2, 5.0, 20.0, 50.0)).is_empty());
+    }
+
+    #[test]
+    fn test_wrap_angle() {
+        assert!((wrap_angle(3.0 * FRAC_PI_2) + FRAC_PI_2).abs() < 1e-9);
+        assert!((wrap_angle(-3.0 * FRAC_PI_2) - FRAC_PI_2).abs() < 1e-9);
+        assert_eq!(wrap_angle(0.5), 0.5);
+    }
+
+    #[test]
+    fn test_virtual_joystick() {
+        let mut joystick = VirtualJoystick::new((50.0, 50.0), 20.0);
+
+        // outside the base
+        assert!(!joystick.handle(&down(1, 0.0, 0.0, 0.0)));
+        assert!(joystick.handle(&down(2, 60.0, 50.0, 0.0)));
+        assert_eq!(joystick.value(), (0.5, 0.0));
+        assert!(!joystick.handle(&touch_move(1, 60.0, 60.0, 10.0)));
+
+        // clamped to the base, up is positive
+        assert!(joystick.handle(&touch_move(2, 50.0, 0.0, 10.0)));
+        assert_eq!(joystick.value(), (0.0, 1.0));
+        assert!(joystick.handle(&up(2, 50.0, 0.0, 20.0)));
+        assert!(!joystick.is_active());
+        assert_eq!(joystick.value(), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_draw_virtual_joystick() {
+        let mut renderer = Renderer::new(MemoryBackend::new(48, 36));
+        let joystick = VirtualJoystick::bottom_left(48.0, 36.0);
+
+        joystick.draw(&mut renderer);
+
+        let points: Vec<_> = renderer
+            .backend()
+            .commands
+            .iter()
+            .filter_map(|command| match command {
+                DrawCommand::Point { x, y, radius, .. } => Some((*x, *y, *radius)),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(points, [(6.75, 29.25, 4.5), (6.75, 29.25, 1.8)]);
+    }
+}
@@ -1,6 +1,6 @@
 use wasm_bindgen::prelude::*;
 
-mod game;
+pub mod game;
 
 #[wasm_bindgen(start)]
 pub fn start() {